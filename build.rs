@@ -1,9 +1,7 @@
 fn main() {
     use std::path::PathBuf;
 
-    if !cfg!(target_os = "windows") {
-        return;
-    }
+    println!("cargo::rustc-check-cfg=cfg(embedded_icon)");
 
     let manifest = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
     let base = PathBuf::from(&manifest);
@@ -24,6 +22,14 @@ fn main() {
 
     if let Some(path) = found {
         let p = path.to_string_lossy().to_string();
+        // 让 main.rs 通过 include_bytes! 内嵌同一个图标作为窗口图标
+        println!("cargo:rustc-env=SILENCER_ICON={}", p);
+        println!("cargo:rustc-cfg=embedded_icon");
+
+        // 可执行文件资源图标只在 Windows 上嵌入
+        if std::env::var("CARGO_CFG_WINDOWS").is_err() {
+            return;
+        }
         match winres::WindowsResource::new().set_icon(&p).compile() {
             Ok(_) => println!("cargo:warning=Embedded icon from {}", p),
             Err(e) => println!("cargo:warning=Failed to embed icon: {}", e),
@@ -0,0 +1,54 @@
+//! 周期同步的开销：稳态下（前台与会话都未变化）每次同步不应再调用后端修改静音，
+//! 与每次都切换前台、所有会话都需要修改的情况对比。
+//!
+//! 运行：`cargo bench -p silencer-core --bench enforcement`
+
+use std::hint::black_box;
+use std::sync::Arc;
+
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use silencer_core::Config;
+use silencer_core::audio::AudioManager;
+use silencer_core::audio::fake::FakeBackend;
+
+const FOREGROUND: u32 = 1;
+
+/// `count` 个会话，全部在黑名单中，PID 从 1 开始
+fn setup(count: u32) -> (Arc<FakeBackend>, AudioManager, Config) {
+    let backend = Arc::new(FakeBackend::new());
+    let mut config = Config::default();
+    for pid in 1..=count {
+        let name = format!("app{}.exe", pid);
+        backend.add_session(&name, pid);
+        config.list.insert(name);
+    }
+    let manager = AudioManager::with_backend(Box::new(Arc::clone(&backend)));
+    (backend, manager, config)
+}
+
+fn enforcement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_mute_status");
+    for count in [8, 64] {
+        group.bench_with_input(BenchmarkId::new("steady_state", count), &count, |b, &count| {
+            let (backend, manager, config) = setup(count);
+            manager.update_mute_status(&config, true, FOREGROUND).unwrap();
+            let calls = backend.mute_calls();
+            b.iter(|| manager.update_mute_status(black_box(&config), true, black_box(FOREGROUND)).unwrap());
+            assert_eq!(backend.mute_calls(), calls, "稳态同步不应修改任何会话");
+        });
+
+        group.bench_with_input(BenchmarkId::new("focus_toggling", count), &count, |b, &count| {
+            let (_backend, manager, config) = setup(count);
+            let mut foreground = FOREGROUND;
+            b.iter(|| {
+                // 每次都切到另一个会话，使两个会话的期望状态发生变化
+                foreground = foreground % count + 1;
+                manager.update_mute_status(black_box(&config), true, black_box(foreground)).unwrap()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, enforcement);
+criterion_main!(benches);
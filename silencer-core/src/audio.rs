@@ -0,0 +1,1547 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config::{BackgroundAction, Config, Fade};
+use crate::decision::{self, Foreground};
+use crate::fade::Ramp;
+use crate::journal::{self, Journal, JournalEntry, Repair};
+use crate::process::tree::ProcessTree;
+use crate::process::{ProcessInfo, ProcessInfoProvider};
+use crate::scheduler::FORCED_SYNC;
+
+pub mod fake;
+#[cfg(target_os = "linux")]
+pub mod pulse;
+#[cfg(windows)]
+pub mod wasapi;
+
+#[derive(Clone, Debug, Default)]
+pub struct AudioSessionInfo {
+    pub id: String, // 后端内部的会话标识（WASAPI 下为会话实例 ID）
+    pub name: String,
+    pub pid: u32,
+    pub process_start_ms: Option<u64>, // 进程启动时间（Unix 毫秒），与 PID 一起识别进程，无法获取时为 None
+    pub parent_pid: Option<u32>,      // 父进程 PID，无法获取时为 None
+    pub exe_path: Option<String>,     // 可执行文件完整路径，无法获取时为 None
+    pub company_name: Option<String>, // 可执行文件版本信息中的公司名称
+    pub product_name: Option<String>, // 可执行文件版本信息中的产品名称
+    pub window_title: String,
+    pub display_name: String, // 用于显示的名称，包含 (1), (2) 等
+    pub device_id: String,    // 会话所在播放设备的 ID
+    pub device_name: String,  // 播放设备的友好名称，用于界面分组
+}
+
+impl AudioSessionInfo {
+    /// 用查询到的进程信息填充启动时间、父进程、路径与版本信息
+    pub fn set_process(&mut self, process: &ProcessInfo) {
+        self.process_start_ms = process.start_time.and_then(journal::unix_ms);
+        self.parent_pid = process.parent_pid;
+        self.exe_path = process.exe_path.as_ref().map(|path| path.to_string_lossy().into_owned());
+        self.company_name = process.company_name.clone();
+        self.product_name = process.product_name.clone();
+    }
+}
+
+/// 会话可被我们修改、并需要在退出时恢复的状态
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SessionState {
+    pub muted: bool,
+    pub volume: f32,
+}
+
+#[derive(Debug)]
+pub enum AudioError {
+    /// 当前平台没有可用的音频后端
+    Unsupported,
+    /// 会话已不存在（进程退出或设备被移除）
+    SessionNotFound(String),
+    #[cfg(windows)]
+    Windows(windows::core::Error),
+    /// pactl 调用失败或输出无法解析
+    #[cfg(target_os = "linux")]
+    Pulse(String),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::Unsupported => write!(f, "当前平台没有可用的音频后端"),
+            AudioError::SessionNotFound(id) => write!(f, "找不到音频会话 {}", id),
+            #[cfg(windows)]
+            AudioError::Windows(e) => write!(f, "{}", e),
+            #[cfg(target_os = "linux")]
+            AudioError::Pulse(e) => write!(f, "PulseAudio: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for AudioError {}
+
+#[cfg(windows)]
+impl From<windows::core::Error> for AudioError {
+    fn from(e: windows::core::Error) -> Self {
+        AudioError::Windows(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, AudioError>;
+
+/// 会话出现、状态变化或断开时调用的通知函数（可能在任意线程上被调用）
+pub type SessionNotifier = Arc<dyn Fn() + Send + Sync>;
+
+/// 用户（或其它程序）在系统音量合成器中对会话做的修改，不包括我们自己的修改
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExternalChange {
+    pub session_id: String,
+    pub muted: bool,
+    pub volume: f32,
+}
+
+/// 会话被外部修改时调用的通知函数（可能在任意线程上被调用）
+pub type ChangeNotifier = Arc<dyn Fn(ExternalChange) + Send + Sync>;
+
+/// 音频后端：静音引擎只通过它枚举会话、读写静音和音量，不直接接触平台 API
+pub trait AudioBackend {
+    /// 枚举当前的音频会话（不包含 PID 为 0 的系统会话）
+    fn sessions(&self) -> Result<Vec<AudioSessionInfo>>;
+
+    /// 会话的静音状态。后端可以返回最近一次 [`AudioBackend::sessions`] 枚举时的快照（加上之后通过本后端所做的修改），
+    /// 需要最新状态时先重新枚举；[`AudioManager`] 总是在同步开始时枚举，之后才读取
+    fn get_mute(&self, id: &str) -> Result<bool>;
+
+    fn set_mute(&self, id: &str, muted: bool) -> Result<()>;
+
+    /// 会话的音量，1.0 表示 100%。与 [`AudioBackend::get_mute`] 一样可以返回最近一次枚举时的快照
+    fn get_volume(&self, id: &str) -> Result<f32>;
+
+    fn set_volume(&self, id: &str, volume: f32) -> Result<()>;
+
+    /// 将会话恢复到之前记录的状态
+    fn restore(&self, id: &str, state: &SessionState) -> Result<()> {
+        self.set_volume(id, state.volume)?;
+        self.set_mute(id, state.muted)
+    }
+
+    /// 开始监听会话变化，变化时调用 `notify`。返回 `false` 表示后端不支持事件，调用方需要定期轮询
+    fn watch_sessions(&self, notify: SessionNotifier) -> Result<bool> {
+        let _ = notify;
+        Ok(false)
+    }
+
+    /// 开始监听会话被外部修改静音或音量，修改时调用 `notify`。返回 `false` 表示后端无法区分我们自己的修改与外部修改
+    fn watch_external_changes(&self, notify: ChangeNotifier) -> Result<bool> {
+        let _ = notify;
+        Ok(false)
+    }
+}
+
+impl<T: AudioBackend + ?Sized> AudioBackend for Arc<T> {
+    fn sessions(&self) -> Result<Vec<AudioSessionInfo>> {
+        (**self).sessions()
+    }
+
+    fn get_mute(&self, id: &str) -> Result<bool> {
+        (**self).get_mute(id)
+    }
+
+    fn set_mute(&self, id: &str, muted: bool) -> Result<()> {
+        (**self).set_mute(id, muted)
+    }
+
+    fn get_volume(&self, id: &str) -> Result<f32> {
+        (**self).get_volume(id)
+    }
+
+    fn set_volume(&self, id: &str, volume: f32) -> Result<()> {
+        (**self).set_volume(id, volume)
+    }
+
+    fn restore(&self, id: &str, state: &SessionState) -> Result<()> {
+        (**self).restore(id, state)
+    }
+
+    fn watch_sessions(&self, notify: SessionNotifier) -> Result<bool> {
+        (**self).watch_sessions(notify)
+    }
+
+    fn watch_external_changes(&self, notify: ChangeNotifier) -> Result<bool> {
+        (**self).watch_external_changes(notify)
+    }
+}
+
+/// 返回当前平台的默认音频后端
+pub fn platform_backend() -> Result<Box<dyn AudioBackend>> {
+    #[cfg(windows)]
+    {
+        Ok(Box::new(wasapi::WasapiBackend::new()?))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        Ok(Box::new(pulse::PulseBackend::new()?))
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        Err(AudioError::Unsupported)
+    }
+}
+
+/// 比较音量时允许的误差，后端保存音量时可能有舍入
+pub(crate) const VOLUME_TOLERANCE: f32 = 0.005;
+
+/// 被我们修改过的会话的标识：设备 ID + 会话 ID
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SessionKey {
+    pub device_id: String,
+    pub session_id: String,
+}
+
+impl SessionKey {
+    pub fn of(session: &AudioSessionInfo) -> Self {
+        Self { device_id: session.device_id.clone(), session_id: session.id.clone() }
+    }
+}
+
+/// 渐变结束后要做的事
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FadeEnd {
+    /// 淡出结束，执行动作
+    Apply(BackgroundAction),
+    /// 淡入结束，回到原始状态
+    Restore,
+}
+
+/// 正在进行的一段渐变
+struct ActiveFade {
+    session_id: String,
+    ramp: Ramp,
+    end: FadeEnd,
+}
+
+/// 失去前台后的宽限期
+#[derive(Default)]
+struct Grace {
+    // 上一次同步后保持有声的会话，只有它们在需要静音时才有宽限期；新出现的后台会话立即执行动作
+    audible: HashSet<SessionKey>,
+    // 宽限期内的会话及其到期时间
+    deadlines: HashMap<SessionKey, Instant>,
+}
+
+impl Grace {
+    /// 按本次计划更新会话的宽限期，返回是否仍应保持有声
+    fn keep_audible(&mut self, key: &SessionKey, mute: bool, delay: Duration, now: Instant) -> bool {
+        let held = mute
+            && !delay.is_zero()
+            && match self.deadlines.get(key) {
+                Some(deadline) => now < *deadline,
+                None if self.audible.contains(key) => {
+                    self.deadlines.insert(key.clone(), now + delay);
+                    true
+                }
+                None => false,
+            };
+        if !held {
+            self.deadlines.remove(key);
+        }
+        if mute && !held {
+            self.audible.remove(key);
+        } else {
+            self.audible.insert(key.clone());
+        }
+        held
+    }
+
+    fn forget(&mut self, key: &SessionKey) {
+        self.audible.remove(key);
+        self.deadlines.remove(key);
+    }
+}
+
+pub struct AudioManager {
+    backend: Box<dyn AudioBackend>,
+    // 保存：当我们修改某个会话的静音状态时，记录其原始状态，在不再需要静音、停止运行或退出时恢复。
+    // 记录只对当前被我们改动的会话存在；设备消失时记录仍然保留，设备重新出现后同一进程的新会话沿用原来的记录。
+    // volume_applied 表示是否修改过音量；只改过静音时恢复不应覆盖用户在此期间调整的音量
+    saved_states: Mutex<HashMap<SessionKey, JournalEntry>>,
+    // 用户手动修改后暂停执行规则的会话，值为暂停时的前台进程 PID，前台切换后恢复执行
+    paused: Mutex<HashMap<SessionKey, u32>>,
+    // 最近一次同步中由我们静音或降低音量的会话及执行的动作。期望状态未变时不再调用后端，避免每次同步都向其它混音器程序发送音量变化通知
+    applied: Mutex<HashMap<SessionKey, BackgroundAction>>,
+    // 正在淡入或淡出的会话。渐变期间会话的记录保持不变，停止运行或退出时仍按原始状态恢复
+    fades: Mutex<HashMap<SessionKey, ActiveFade>>,
+    grace: Mutex<Grace>,
+    // 后端能否上报外部修改；不能时需要读取实际状态，确认会话没有被改回后才能跳过
+    reports_external: AtomicBool,
+    // 启用后每次记录变化都写入磁盘，用于异常退出后的修复
+    journal: Option<Journal>,
+    // 用于记录进程启动时间，以及按前台关系判断会话是否位于前台
+    process_info: Option<Box<dyn ProcessInfoProvider>>,
+    // 最近一次查询的没有音频会话的前台进程身份，按 (PID, 启动时间) 复用
+    foreground_process: Mutex<Option<(u32, Option<SystemTime>, AudioSessionInfo)>>,
+}
+
+impl AudioManager {
+    pub fn new() -> Result<Self> {
+        Ok(Self::with_backend(platform_backend()?))
+    }
+
+    pub fn with_backend(backend: Box<dyn AudioBackend>) -> Self {
+        Self {
+            backend,
+            saved_states: Mutex::new(HashMap::new()),
+            paused: Mutex::new(HashMap::new()),
+            applied: Mutex::new(HashMap::new()),
+            fades: Mutex::new(HashMap::new()),
+            grace: Mutex::new(Grace::default()),
+            reports_external: AtomicBool::new(false),
+            journal: None,
+            process_info: None,
+            foreground_process: Mutex::new(None),
+        }
+    }
+
+    /// 启用持久化日志。设置了进程信息提供者时日志会记录进程启动时间以识别进程身份
+    pub fn with_journal(mut self, journal: Journal) -> Self {
+        self.journal = Some(journal);
+        self
+    }
+
+    /// 设置进程信息提供者，用于识别进程身份，以及在配置启用前台关系时查询进程树
+    pub fn with_process_info(mut self, process_info: Box<dyn ProcessInfoProvider>) -> Self {
+        self.process_info = Some(process_info);
+        self
+    }
+
+    fn start_time_ms(&self, pid: u32) -> Option<u64> {
+        self.process_info.as_ref()?.start_time(pid).and_then(journal::unix_ms)
+    }
+
+    /// 以当前的前台进程调用 `f`。配置启用了前台关系时附带本次使用的进程树，配置了联动分组时附带前台进程的身份
+    pub fn with_foreground<R>(
+        &self,
+        config: &Config,
+        foreground_pid: u32,
+        sessions: &[AudioSessionInfo],
+        f: impl FnOnce(Foreground) -> R,
+    ) -> R {
+        let tree = match &self.process_info {
+            Some(process_info) if config.uses_foreground_relations() => Some(ProcessTree::new(process_info.as_ref())),
+            _ => None,
+        };
+        let process = if config.groups.is_empty() { None } else { self.foreground_identity(foreground_pid, sessions) };
+        f(Foreground { pid: foreground_pid, tree: tree.as_ref(), process: process.as_ref() })
+    }
+
+    /// 前台进程的身份：优先使用它的会话，没有会话时通过进程信息提供者查询
+    fn foreground_identity(&self, pid: u32, sessions: &[AudioSessionInfo]) -> Option<AudioSessionInfo> {
+        if pid == 0 {
+            return None;
+        }
+        if let Some(session) = sessions.iter().find(|session| session.pid == pid) {
+            return Some(session.clone());
+        }
+        let process_info = self.process_info.as_ref()?;
+        let start_time = process_info.start_time(pid);
+        let mut cached = self.foreground_process.lock().unwrap();
+        if let Some((cached_pid, cached_start, identity)) = &*cached
+            && *cached_pid == pid
+            && *cached_start == start_time
+        {
+            return Some(identity.clone());
+        }
+        let info = process_info.process_info(pid)?;
+        let mut identity = AudioSessionInfo { name: info.name.clone(), pid, ..Default::default() };
+        identity.set_process(&info);
+        *cached = Some((pid, start_time, identity.clone()));
+        Some(identity)
+    }
+
+    /// 根据上次运行遗留的日志恢复未能还原的会话（例如程序崩溃、被结束或注销），返回修复的会话并清空日志。
+    /// 应在修改任何会话之前调用
+    pub fn repair_from_journal(&self) -> Result<Vec<Repair>> {
+        let Some(journal) = &self.journal else {
+            return Ok(Vec::new());
+        };
+        let entries = journal.load();
+        if entries.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let sessions = self.backend.sessions()?;
+        let mut repairs = Vec::new();
+        for entry in &entries {
+            let start_ms = |session: &AudioSessionInfo| session.process_start_ms.or_else(|| self.start_time_ms(session.pid));
+            let same_process = |session: &AudioSessionInfo| {
+                session.pid == entry.pid && (entry.process_start_ms.is_none() || start_ms(session) == entry.process_start_ms)
+            };
+            let state = |session: &AudioSessionInfo| -> Result<SessionState> {
+                Ok(SessionState { muted: self.backend.get_mute(&session.id)?, volume: self.backend.get_volume(&session.id)? })
+            };
+            let exact = sessions
+                .iter()
+                .find(|session| session.id == entry.session_id && session.device_id == entry.device_id && same_process(session));
+
+            if let Some(session) = exact {
+                // 用户在崩溃后已经自己修改过会话，保留用户的设置
+                if entry.applied.is_some() && !entry.shows_applied(state(session)?) {
+                    continue;
+                }
+                let original = entry.original_state();
+                let restored = if entry.volume_applied {
+                    self.backend.restore(&session.id, &original)?;
+                    original
+                } else {
+                    self.backend.set_mute(&session.id, original.muted)?;
+                    SessionState { muted: original.muted, volume: self.backend.get_volume(&session.id)? }
+                };
+                repairs.push(Repair { process_name: entry.process_name.clone(), pid: session.pid, device_id: session.device_id.clone(), restored });
+                continue;
+            }
+
+            // 原进程已退出：系统会为同一程序的新会话沿用上次的静音状态与音量。只恢复在原进程之后启动、
+            // 且状态仍与我们执行的动作一致的会话，其它会话的静音或音量是用户自己设置的
+            let restarted = |session: &AudioSessionInfo| match (start_ms(session), entry.process_start_ms) {
+                (Some(start), Some(original_start)) => start > original_start,
+                _ => true,
+            };
+            for session in sessions.iter().filter(|session| {
+                session.device_id == entry.device_id
+                    && session.name.eq_ignore_ascii_case(&entry.process_name)
+                    && !same_process(session)
+                    && restarted(session)
+            }) {
+                let current = state(session)?;
+                if !entry.shows_applied(current) {
+                    continue;
+                }
+                let restored = if entry.volume_applied {
+                    entry.original_state()
+                } else {
+                    SessionState { muted: entry.muted, volume: current.volume }
+                };
+                if restored == current {
+                    continue;
+                }
+                self.backend.restore(&session.id, &restored)?;
+                repairs.push(Repair { process_name: entry.process_name.clone(), pid: session.pid, device_id: session.device_id.clone(), restored });
+            }
+        }
+
+        let _ = journal.store(&[]);
+        Ok(repairs)
+    }
+
+    /// 把当前记录写入日志
+    fn persist(&self, saved: &HashMap<SessionKey, JournalEntry>) {
+        if let Some(journal) = &self.journal {
+            let entries: Vec<JournalEntry> = saved.values().cloned().collect();
+            let _ = journal.store(&entries);
+        }
+    }
+
+    /// 见 [`AudioBackend::watch_sessions`]
+    pub fn watch_sessions(&self, notify: SessionNotifier) -> Result<bool> {
+        self.backend.watch_sessions(notify)
+    }
+
+    /// 见 [`AudioBackend::watch_external_changes`]
+    pub fn watch_external_changes(&self, notify: ChangeNotifier) -> Result<bool> {
+        let watching = self.backend.watch_external_changes(notify)?;
+        self.reports_external.store(watching, Ordering::Relaxed);
+        Ok(watching)
+    }
+
+    /// 会话被外部修改后调用，使下一次同步重新检查并应用该会话的静音状态。
+    /// 外部修改通知只带有会话 ID，因此不论会话位于哪个设备都一并清除
+    pub fn forget_applied(&self, session_id: &str) {
+        self.applied.lock().unwrap().retain(|key, _| key.session_id != session_id);
+    }
+
+    /// 暂停对该会话执行规则，直到前台窗口不再是 `foreground_pid`
+    pub fn pause(&self, session: &AudioSessionInfo, foreground_pid: u32) {
+        self.paused.lock().unwrap().insert(SessionKey::of(session), foreground_pid);
+    }
+
+    pub fn is_paused(&self, session: &AudioSessionInfo) -> bool {
+        self.paused.lock().unwrap().contains_key(&SessionKey::of(session))
+    }
+
+    pub fn get_active_sessions(&self) -> Result<Vec<AudioSessionInfo>> {
+        let mut sessions = self.backend.sessions()?;
+        number_duplicates(&mut sessions);
+        Ok(sessions)
+    }
+
+    /// 按配置同步所有会话的静音状态，只对期望状态发生变化的会话调用后端。
+    /// 返回本次枚举到的会话（与 [`AudioManager::get_active_sessions`] 相同），调用方可据此更新窗口标题等会变化的信息
+    pub fn update_mute_status(&self, config: &Config, enabled: bool, foreground_pid: u32) -> Result<Vec<AudioSessionInfo>> {
+        self.update_mute_status_at(config, enabled, foreground_pid, Instant::now())
+    }
+
+    /// 同 [`AudioManager::update_mute_status`]，以 `now` 作为淡入淡出的起点与失去前台后宽限期的计时起点
+    pub fn update_mute_status_at(&self, config: &Config, enabled: bool, foreground_pid: u32, now: Instant) -> Result<Vec<AudioSessionInfo>> {
+        let result = self.sync(config, enabled, foreground_pid, now);
+        if result.is_err() {
+            // 同步失败时已到期的宽限期没有被处理，推迟一个周期同步间隔再重试，
+            // 否则调用方会在同一个已经过去的到期时间上不停地同步
+            for deadline in self.grace.lock().unwrap().deadlines.values_mut() {
+                if *deadline <= now {
+                    *deadline = now + FORCED_SYNC;
+                }
+            }
+        }
+        result
+    }
+
+    fn sync(&self, config: &Config, enabled: bool, foreground_pid: u32, now: Instant) -> Result<Vec<AudioSessionInfo>> {
+        let mut sessions = self.backend.sessions()?;
+        let paused = {
+            let mut paused = self.paused.lock().unwrap();
+            paused.retain(|_, paused_foreground| enabled && *paused_foreground == foreground_pid);
+            paused.clone()
+        };
+        let mut applied = self.applied.lock().unwrap();
+        applied.retain(|key, _| sessions.iter().any(|session| SessionKey::of(session) == *key));
+        let mut fades = self.fades.lock().unwrap();
+        fades.retain(|key, _| sessions.iter().any(|session| SessionKey::of(session) == *key));
+        let mut grace = self.grace.lock().unwrap();
+        if enabled {
+            let Grace { audible, deadlines } = &mut *grace;
+            audible.retain(|key| sessions.iter().any(|session| SessionKey::of(session) == *key));
+            deadlines.retain(|key, _| audible.contains(key));
+        } else {
+            // 停止运行期间没有会话失去前台，重新运行时后台会话立即执行动作
+            *grace = Grace::default();
+        }
+        let plan = self.with_foreground(config, foreground_pid, &sessions, |foreground| {
+            decision::plan(&sessions, config, enabled, foreground)
+        });
+        for (session, decision) in sessions.iter().zip(plan) {
+            let key = SessionKey::of(session);
+            if paused.contains_key(&key) {
+                applied.remove(&key);
+                grace.forget(&key);
+                if fades.remove(&key).is_some()
+                    && let Some(volume) = self.saved_states.lock().unwrap().get(&key).map(|original| original.volume)
+                {
+                    // 渐变中途暂停：回到原始音量，不停在渐变的中间；静音状态保持用户的设置
+                    self.backend.set_volume(&session.id, volume)?;
+                }
+                continue;
+            }
+            if enabled && grace.keep_audible(&key, decision.mute, decision.mute_delay, now) {
+                continue;
+            }
+            // 方向相反的渐变会被取消，新的渐变从当前音量开始
+            let fading = fades.get(&key).map(|fade| fade.end);
+            if decision.mute {
+                if fading == Some(FadeEnd::Apply(decision.action)) {
+                    continue;
+                }
+                let previous = applied.get(&key).copied();
+                if fading.is_none()
+                    && previous == Some(decision.action)
+                    && (self.reports_external.load(Ordering::Relaxed) || self.still_applied(session, decision.action)?)
+                {
+                    continue;
+                }
+                self.save_original_state(session, &sessions);
+                if config.fade_out.duration_ms > 0
+                    && let Some(ramp) = self.start_ramp(session, previous, config.fade_out, now, |original| {
+                        decision.action.volume(original).unwrap_or(0.0)
+                    })?
+                {
+                    applied.remove(&key);
+                    fades.insert(key, ActiveFade { session_id: session.id.clone(), ramp, end: FadeEnd::Apply(decision.action) });
+                    continue;
+                }
+                fades.remove(&key);
+                let switched = previous
+                    .filter(|previous| *previous != decision.action)
+                    .and_then(|_| self.saved_states.lock().unwrap().get(&key).cloned());
+                if let Some(original) = switched {
+                    // 换用另一种动作：先回到原始状态，避免静音与降低音量叠加
+                    self.restore_session(&session.id, &original)?;
+                }
+                self.apply_action(session, decision.action)?;
+                applied.insert(key, decision.action);
+            } else {
+                if fading == Some(FadeEnd::Restore) {
+                    continue;
+                }
+                let previous = applied.remove(&key);
+                if config.fade_in.duration_ms > 0
+                    && (previous.is_some() || fading.is_some())
+                    && let Some(ramp) = self.start_ramp(session, previous, config.fade_in, now, |original| original)?
+                {
+                    fades.insert(key, ActiveFade { session_id: session.id.clone(), ramp, end: FadeEnd::Restore });
+                    continue;
+                }
+                fades.remove(&key);
+                // 不再需要静音：回到我们修改之前的状态，而不是强制取消静音
+                self.restore_saved_state(session, &sessions)?;
+            }
+        }
+        number_duplicates(&mut sessions);
+        Ok(sessions)
+    }
+
+    /// 对会话执行动作。降低音量以记录的原始音量为准
+    fn apply_action(&self, session: &AudioSessionInfo, action: BackgroundAction) -> Result<()> {
+        let original = self.update_record(&SessionKey::of(session), |entry| {
+            entry.applied = Some(action);
+            entry.volume_applied |= action != BackgroundAction::Mute;
+        });
+        match (action, original) {
+            (BackgroundAction::Mute, _) => self.backend.set_mute(&session.id, true),
+            (_, Some(original)) => self.backend.set_volume(&session.id, action.volume(original.volume).unwrap_or(original.volume)),
+            // 没能记录原始状态时无法恢复音量，不做修改
+            (_, None) => Ok(()),
+        }
+    }
+
+    /// 修改会话前更新其记录（先写日志再修改会话）：执行的动作，以及是否修改了音量（恢复时才会回到原始音量）。
+    /// 返回更新后的记录，没有记录时返回 None
+    fn update_record(&self, key: &SessionKey, update: impl FnOnce(&mut JournalEntry)) -> Option<JournalEntry> {
+        let mut saved = self.saved_states.lock().unwrap();
+        let entry = saved.get_mut(key)?;
+        let before = entry.clone();
+        update(entry);
+        let entry = entry.clone();
+        if entry != before {
+            self.persist(&saved);
+        }
+        Some(entry)
+    }
+
+    /// 从会话当前听到的音量开始一段渐变，终点由 `to` 根据原始音量给出。
+    /// 没有记录原始状态，或用户原本就静音了该会话（听不到渐变）时返回 None，由调用方立即切换
+    fn start_ramp(
+        &self,
+        session: &AudioSessionInfo,
+        previous: Option<BackgroundAction>,
+        fade: Fade,
+        now: Instant,
+        to: impl FnOnce(f32) -> f32,
+    ) -> Result<Option<Ramp>> {
+        let key = SessionKey::of(session);
+        let Some(original) = self.saved_states.lock().unwrap().get(&key).cloned() else {
+            return Ok(None);
+        };
+        if original.muted {
+            return Ok(None);
+        }
+        // 渐变期间会话的状态不断变化，不再是某个动作的结果
+        self.update_record(&key, |entry| {
+            entry.applied = None;
+            entry.volume_applied = true;
+        });
+        let from = if previous == Some(BackgroundAction::Mute) {
+            // 已被我们静音：先把音量降到 0 再取消静音，避免以原音量响一下
+            self.backend.set_volume(&session.id, 0.0)?;
+            self.backend.set_mute(&session.id, false)?;
+            0.0
+        } else {
+            self.backend.get_volume(&session.id)?
+        };
+        Ok(Some(Ramp::new(from, to(original.volume), now, fade)))
+    }
+
+    /// 会话是否处于失去前台后的宽限期内（计划静音但仍保持有声）
+    pub fn in_grace_period(&self, session: &AudioSessionInfo) -> bool {
+        self.grace.lock().unwrap().deadlines.contains_key(&SessionKey::of(session))
+    }
+
+    /// 最早结束的宽限期，到期时需要再同步一次才能执行动作
+    pub fn next_grace_deadline(&self) -> Option<Instant> {
+        self.grace.lock().unwrap().deadlines.values().min().copied()
+    }
+
+    /// 是否有淡入淡出正在进行，进行中需要按 [`crate::scheduler::FADE_STEP`] 调用 [`AudioManager::step_fades`]
+    pub fn is_fading(&self) -> bool {
+        !self.fades.lock().unwrap().is_empty()
+    }
+
+    /// 会话是否正在渐变。渐变期间会话的音量（以及淡出静音前的非静音状态）由我们不断修改
+    pub fn is_session_fading(&self, session: &AudioSessionInfo) -> bool {
+        self.fades.lock().unwrap().contains_key(&SessionKey::of(session))
+    }
+
+    /// 推进正在进行的渐变。淡出结束后执行动作，淡入结束后回到原始状态并丢弃记录。
+    /// 某一步设置音量失败时不再继续渐变，立即执行结束时的动作；动作也失败时返回错误，
+    /// 会话没有记为已执行，下一次同步会重新处理
+    pub fn step_fades(&self, now: Instant) -> Result<()> {
+        let mut applied = self.applied.lock().unwrap();
+        let mut finished = Vec::new();
+        self.fades.lock().unwrap().retain(|key, fade| {
+            let stepped = self.backend.set_volume(&fade.session_id, fade.ramp.volume_at(now)).is_ok();
+            if !stepped || fade.ramp.is_finished(now) {
+                finished.push((key.clone(), fade.session_id.clone(), fade.end));
+                return false;
+            }
+            true
+        });
+
+        let mut result = Ok(());
+        for (key, session_id, end) in finished {
+            if let Err(e) = self.finish_fade(&mut applied, key, &session_id, end) {
+                result = Err(e);
+            }
+        }
+        result
+    }
+
+    fn finish_fade(&self, applied: &mut HashMap<SessionKey, BackgroundAction>, key: SessionKey, session_id: &str, end: FadeEnd) -> Result<()> {
+        match end {
+            FadeEnd::Apply(BackgroundAction::Mute) => {
+                self.update_record(&key, |entry| entry.applied = Some(BackgroundAction::Mute));
+                self.backend.set_mute(session_id, true)?;
+                // 静音后把音量放回原始值，之后无论由谁取消静音都不会停在渐变的终点
+                let original = self.saved_states.lock().unwrap().get(&key).map(|original| original.volume);
+                if let Some(volume) = original {
+                    self.backend.set_volume(session_id, volume)?;
+                }
+                applied.insert(key, BackgroundAction::Mute);
+            }
+            FadeEnd::Apply(action) => {
+                let original = self.update_record(&key, |entry| entry.applied = Some(action));
+                if let Some(volume) = original.and_then(|original| action.volume(original.volume)) {
+                    // 渐变的最后一步可能没能设置成功
+                    self.backend.set_volume(session_id, volume)?;
+                }
+                applied.insert(key, action);
+            }
+            FadeEnd::Restore => {
+                let original = self.saved_states.lock().unwrap().get(&key).cloned();
+                if let Some(original) = original {
+                    // 恢复成功后才删除记录
+                    self.restore_session(session_id, &original)?;
+                    let mut saved = self.saved_states.lock().unwrap();
+                    if saved.remove(&key).is_some() {
+                        self.persist(&saved);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 后端不上报外部修改时，确认会话仍处于执行动作后的状态
+    fn still_applied(&self, session: &AudioSessionInfo, action: BackgroundAction) -> Result<bool> {
+        let original = self.saved_states.lock().unwrap().get(&SessionKey::of(session)).map(|original| original.volume);
+        match original.and_then(|volume| action.volume(volume)) {
+            Some(volume) => Ok((self.backend.get_volume(&session.id)? - volume).abs() < VOLUME_TOLERANCE),
+            None => self.backend.get_mute(&session.id),
+        }
+    }
+
+    /// 把会话恢复到记录的原始状态。恢复成功后才删除记录，后端调用失败时保留记录以便之后重试
+    fn restore_saved_state(&self, session: &AudioSessionInfo, sessions: &[AudioSessionInfo]) -> Result<()> {
+        let key = SessionKey::of(session);
+        let original = {
+            let mut saved = self.saved_states.lock().unwrap();
+            match saved.get(&key) {
+                Some(original) => original.clone(),
+                None => {
+                    let Some(mut previous) = take_reappeared(&mut saved, session, sessions) else {
+                        return Ok(());
+                    };
+                    // 设备重新出现后会话 ID 变了，记录改按新的会话保存
+                    previous.session_id = session.id.clone();
+                    saved.insert(key.clone(), previous.clone());
+                    previous
+                }
+            }
+        };
+        self.restore_session(&session.id, &original)?;
+        let mut saved = self.saved_states.lock().unwrap();
+        saved.remove(&key);
+        self.persist(&saved);
+        Ok(())
+    }
+
+    fn restore_session(&self, id: &str, original: &JournalEntry) -> Result<()> {
+        if original.volume_applied {
+            self.backend.restore(id, &original.original_state())
+        } else {
+            self.backend.set_mute(id, original.muted)
+        }
+    }
+
+    /// 在首次修改某个会话前记录其原始状态
+    fn save_original_state(&self, session: &AudioSessionInfo, sessions: &[AudioSessionInfo]) {
+        let key = SessionKey::of(session);
+        let mut saved = self.saved_states.lock().unwrap();
+        if saved.contains_key(&key) {
+            return;
+        }
+        if let Some(mut previous) = take_reappeared(&mut saved, session, sessions) {
+            // 设备断开后重新出现，会话 ID 变了，但当前状态可能仍是我们设置的，沿用原来的记录
+            previous.session_id = session.id.clone();
+            saved.insert(key, previous);
+            self.persist(&saved);
+            return;
+        }
+        if let (Ok(muted), Ok(volume)) = (self.backend.get_mute(&session.id), self.backend.get_volume(&session.id)) {
+            let process_start_ms = session.process_start_ms.or_else(|| self.start_time_ms(session.pid));
+            let entry = JournalEntry {
+                device_id: session.device_id.clone(),
+                session_id: session.id.clone(),
+                pid: session.pid,
+                process_name: session.name.clone(),
+                process_start_ms,
+                muted,
+                volume,
+                volume_applied: false,
+                applied: None,
+            };
+            saved.insert(key, entry);
+            // 先写日志再修改会话，保证崩溃时日志中一定有这条记录
+            self.persist(&saved);
+        }
+    }
+
+    /// 在停止运行或程序退出时，将所有被记录修改过的会话恢复到原始状态（包括用户原本就静音的会话）
+    pub fn restore_saved_states(&self) -> Result<()> {
+        let mut errors: Option<AudioError> = None;
+        let sessions = self.backend.sessions()?;
+        self.applied.lock().unwrap().clear();
+        *self.grace.lock().unwrap() = Grace::default();
+        // 渐变中的会话按记录回到原始音量，不会停在中间值
+        self.fades.lock().unwrap().clear();
+
+        for session in &sessions {
+            if let Err(e) = self.restore_saved_state(session, &sessions) {
+                errors = Some(e);
+            }
+        }
+        // 会话已随进程退出或设备拔出而消失的记录无需恢复；恢复失败的会话保留记录，下次停止或启动修复时重试
+        let mut saved = self.saved_states.lock().unwrap();
+        saved.retain(|key, _| sessions.iter().any(|session| SessionKey::of(session) == *key));
+        self.persist(&saved);
+
+        if let Some(e) = errors {
+            Err(e)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 处理重名，确保重复实例有 (n) 标识
+fn number_duplicates(sessions: &mut [AudioSessionInfo]) {
+    let mut total_counts: HashMap<String, usize> = HashMap::new();
+    for s in sessions.iter() {
+        *total_counts.entry(s.name.clone()).or_insert(0) += 1;
+    }
+
+    let mut current_counts: HashMap<String, usize> = HashMap::new();
+    for session in sessions {
+        let total = total_counts.get(&session.name).cloned().unwrap_or(0);
+        if total > 1 {
+            let current = current_counts.entry(session.name.clone()).or_insert(0);
+            *current += 1;
+            session.display_name = format!("{} ({})", session.name, current);
+        } else {
+            session.display_name = session.name.clone();
+        }
+    }
+}
+
+/// 取出同一设备上同一进程、但会话已不在 `sessions` 中的记录（设备断开重连后会话 ID 会改变）
+fn take_reappeared(saved: &mut HashMap<SessionKey, JournalEntry>, session: &AudioSessionInfo, sessions: &[AudioSessionInfo]) -> Option<JournalEntry> {
+    let key = saved
+        .iter()
+        .find(|(key, original)| {
+            key.device_id == session.device_id
+                && original.pid == session.pid
+                && !sessions.iter().any(|live| live.id == key.session_id)
+        })
+        .map(|(key, _)| key.clone())?;
+    saved.remove(&key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::fake::FakeBackend;
+
+    fn manager_with(backend: &Arc<FakeBackend>) -> AudioManager {
+        AudioManager::with_backend(Box::new(Arc::clone(backend)))
+    }
+
+    fn config(list: &[&str], is_whitelist: bool) -> Config {
+        Config { list: list.iter().map(|s| s.to_string()).collect(), is_whitelist, ..Default::default() }
+    }
+
+    #[test]
+    fn blacklist_mutes_listed_background_sessions() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let music = backend.add_session("music.exe", 200);
+        let manager = manager_with(&backend);
+
+        let config = config(&["Game.exe"], false);
+        manager.update_mute_status(&config, true, 200).unwrap();
+        assert!(backend.state(&game).muted);
+        assert!(!backend.state(&music).muted);
+
+        // 切到前台后取消静音
+        manager.update_mute_status(&config, true, 100).unwrap();
+        assert!(!backend.state(&game).muted);
+    }
+
+    #[test]
+    fn whitelist_mutes_everything_else_in_background() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let music = backend.add_session("music.exe", 200);
+        let chat = backend.add_session("chat.exe", 300);
+        let manager = manager_with(&backend);
+
+        let config = config(&["music.exe"], true);
+        manager.update_mute_status(&config, true, 100).unwrap();
+        assert!(!backend.state(&game).muted);
+        assert!(!backend.state(&music).muted);
+        assert!(backend.state(&chat).muted);
+    }
+
+    #[test]
+    fn foreground_relations_use_the_process_tree() {
+        use crate::config::ForegroundRelations;
+        use crate::process::fake::FakeProcesses;
+
+        let backend = Arc::new(FakeBackend::new());
+        let audio_service = backend.add_session("browser.exe", 11);
+        let music = backend.add_session("music.exe", 200);
+        let processes = FakeProcesses::new().with(10, "browser.exe", 0).with(11, "browser.exe", 10).with(200, "music.exe", 0);
+        let manager = manager_with(&backend).with_process_info(Box::new(processes));
+
+        let mut config = config(&[], true);
+        config.foreground_relations = ForegroundRelations { descendants: true, ..Default::default() };
+        // 浏览器窗口在前台，声音来自它的音频服务子进程
+        manager.update_mute_status(&config, true, 10).unwrap();
+        assert!(!backend.state(&audio_service).muted);
+        assert!(backend.state(&music).muted);
+
+        manager.update_mute_status(&config, true, 200).unwrap();
+        assert!(backend.state(&audio_service).muted);
+        assert!(!backend.state(&music).muted);
+    }
+
+    #[test]
+    fn focused_group_member_is_identified_without_a_session() {
+        use crate::config::{AppGroup, GroupMode};
+        use crate::process::fake::FakeProcesses;
+
+        let backend = Arc::new(FakeBackend::new());
+        let music = backend.add_session("music.exe", 300);
+        let processes = FakeProcesses::new().with(200, "discord.exe", 0).with(300, "music.exe", 0).with(400, "browser.exe", 0);
+        let manager = manager_with(&backend).with_process_info(Box::new(processes));
+
+        let mut config = config(&["music.exe"], false);
+        config.groups.push(AppGroup { name: "游戏".to_string(), members: vec!["discord.exe".to_string(), "music.exe".to_string()], mode: GroupMode::AnyFocused });
+        // Discord 没有音频会话，但它在前台时同组的音乐播放器保持有声
+        manager.update_mute_status(&config, true, 200).unwrap();
+        assert!(!backend.state(&music).muted);
+
+        manager.update_mute_status(&config, true, 400).unwrap();
+        assert!(backend.state(&music).muted);
+    }
+
+    fn with_action(mut config: Config, entry: &str, action: BackgroundAction) -> Config {
+        config.set_options(entry, crate::config::RuleOptions { action: Some(action), ..Default::default() });
+        config
+    }
+
+    #[test]
+    fn ducking_lowers_and_restores_the_original_volume() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.set_volume(&game, 0.8).unwrap();
+        let manager = manager_with(&backend);
+        let config = with_action(config(&["game.exe"], false), "game.exe", BackgroundAction::Duck(20));
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+        // 多次同步不会在已降低的音量上继续降低
+        manager.update_mute_status(&config, true, 0).unwrap();
+        assert!((backend.state(&game).volume - 0.16).abs() < 1e-6);
+        assert!(!backend.state(&game).muted);
+        assert_eq!(backend.mute_calls(), 0);
+
+        manager.update_mute_status(&config, true, 100).unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.8 });
+    }
+
+    #[test]
+    fn capping_only_lowers_louder_sessions() {
+        let backend = Arc::new(FakeBackend::new());
+        let loud = backend.add_session("game.exe", 100);
+        let quiet = backend.add_session("game.exe", 101);
+        backend.set_volume(&quiet, 0.2).unwrap();
+        let manager = manager_with(&backend);
+        let config = with_action(config(&["game.exe"], false), "game.exe", BackgroundAction::Cap(30));
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+        assert!((backend.state(&loud).volume - 0.3).abs() < 1e-6);
+        assert!((backend.state(&quiet).volume - 0.2).abs() < 1e-6);
+
+        manager.restore_saved_states().unwrap();
+        assert_eq!(backend.state(&loud).volume, 1.0);
+    }
+
+    #[test]
+    fn switching_actions_starts_from_the_original_state() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let manager = manager_with(&backend);
+
+        manager.update_mute_status(&with_action(config(&["game.exe"], false), "game.exe", BackgroundAction::Duck(50)), true, 0).unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.5 });
+
+        manager.update_mute_status(&config(&["game.exe"], false), true, 0).unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: true, volume: 1.0 });
+
+        manager.restore_saved_states().unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 1.0 });
+    }
+
+    fn with_fades(mut config: Config, duration_ms: u32) -> Config {
+        config.fade_out = Fade { duration_ms, ..Default::default() };
+        config.fade_in = Fade { duration_ms, ..Default::default() };
+        config
+    }
+
+    fn after(start: Instant, ms: u64) -> Instant {
+        start + std::time::Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn fade_out_ramps_down_before_muting() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.set_volume(&game, 0.8).unwrap();
+        let manager = manager_with(&backend);
+        let config = with_fades(config(&["game.exe"], false), 100);
+        let t0 = Instant::now();
+
+        manager.update_mute_status_at(&config, true, 0, t0).unwrap();
+        assert!(manager.is_fading());
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.8 });
+
+        manager.step_fades(after(t0, 50)).unwrap();
+        assert!((backend.state(&game).volume - 0.4).abs() < 1e-6);
+        // 渐变期间的周期同步不会重新开始渐变
+        manager.update_mute_status_at(&config, true, 0, after(t0, 60)).unwrap();
+
+        manager.step_fades(after(t0, 100)).unwrap();
+        assert!(!manager.is_fading());
+        // 淡出结束后静音，音量放回原始值
+        assert_eq!(backend.state(&game), SessionState { muted: true, volume: 0.8 });
+
+        manager.update_mute_status_at(&config, true, 100, after(t0, 200)).unwrap();
+        manager.step_fades(after(t0, 250)).unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.4 });
+        manager.step_fades(after(t0, 300)).unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.8 });
+        assert!(!manager.is_fading());
+    }
+
+    #[test]
+    fn focus_flip_reverses_a_fade_from_the_current_volume() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let manager = manager_with(&backend);
+        let config = with_fades(config(&["game.exe"], false), 100);
+        let t0 = Instant::now();
+
+        manager.update_mute_status_at(&config, true, 0, t0).unwrap();
+        manager.step_fades(after(t0, 40)).unwrap();
+        manager.update_mute_status_at(&config, true, 100, after(t0, 40)).unwrap();
+        manager.step_fades(after(t0, 90)).unwrap();
+        assert!((backend.state(&game).volume - 0.8).abs() < 1e-6);
+
+        manager.step_fades(after(t0, 140)).unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 1.0 });
+    }
+
+    #[test]
+    fn stopping_mid_fade_restores_the_original_volume() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.set_volume(&game, 0.6).unwrap();
+        let manager = manager_with(&backend);
+        let t0 = Instant::now();
+
+        manager.update_mute_status_at(&with_fades(config(&["game.exe"], false), 100), true, 0, t0).unwrap();
+        manager.step_fades(after(t0, 50)).unwrap();
+        manager.restore_saved_states().unwrap();
+        assert!(!manager.is_fading());
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.6 });
+    }
+
+    #[test]
+    fn pausing_mid_fade_restores_the_original_volume() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.set_volume(&game, 0.6).unwrap();
+        let manager = manager_with(&backend);
+        let config = with_fades(config(&["game.exe"], false), 100);
+        let t0 = Instant::now();
+
+        manager.update_mute_status_at(&config, true, 0, t0).unwrap();
+        manager.step_fades(after(t0, 50)).unwrap();
+        manager.pause(&manager.get_active_sessions().unwrap()[0], 0);
+        manager.update_mute_status_at(&config, true, 0, after(t0, 60)).unwrap();
+        assert!(!manager.is_fading());
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.6 });
+    }
+
+    #[test]
+    fn failed_fade_step_is_retried_by_the_next_sync() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let manager = manager_with(&backend);
+        let config = with_fades(config(&["game.exe"], false), 100);
+        let t0 = Instant::now();
+
+        manager.update_mute_status_at(&config, true, 0, t0).unwrap();
+        backend.set_read_only(true);
+        assert!(manager.step_fades(after(t0, 50)).is_err());
+        assert!(!manager.is_fading());
+        backend.set_read_only(false);
+
+        // 渐变没有完成，会话没有记为已静音，下一次同步重新开始淡出
+        manager.update_mute_status_at(&config, true, 0, after(t0, 100)).unwrap();
+        assert!(manager.is_fading());
+        manager.step_fades(after(t0, 200)).unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: true, volume: 1.0 });
+
+        // 淡入失败时保留原始状态，下一次同步直接恢复
+        manager.update_mute_status_at(&config, true, 100, after(t0, 300)).unwrap();
+        backend.set_read_only(true);
+        assert!(manager.step_fades(after(t0, 350)).is_err());
+        backend.set_read_only(false);
+        manager.update_mute_status_at(&config, true, 100, after(t0, 400)).unwrap();
+        assert!(!manager.is_fading());
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 1.0 });
+    }
+
+    #[test]
+    fn failed_restore_keeps_the_saved_state() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.set_volume(&game, 0.7).unwrap();
+        let manager = manager_with(&backend);
+        let config = config(&["game.exe"], false);
+        manager.update_mute_status(&config, true, 0).unwrap();
+
+        backend.set_read_only(true);
+        assert!(manager.update_mute_status(&config, true, 100).is_err());
+        assert!(manager.restore_saved_states().is_err());
+        backend.set_read_only(false);
+
+        manager.restore_saved_states().unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.7 });
+    }
+
+    #[test]
+    fn restore_unmutes_touched_sessions() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let manager = manager_with(&backend);
+
+        let config = config(&["game.exe"], false);
+        manager.update_mute_status(&config, true, 0).unwrap();
+        assert!(backend.state(&game).muted);
+
+        manager.restore_saved_states().unwrap();
+        assert!(!backend.state(&game).muted);
+    }
+
+    #[test]
+    fn restore_skips_sessions_that_already_exited() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let music = backend.add_session("music.exe", 200);
+        let manager = manager_with(&backend);
+
+        let config = config(&["game.exe", "music.exe"], false);
+        manager.update_mute_status(&config, true, 0).unwrap();
+        backend.remove_session(&game);
+
+        manager.restore_saved_states().unwrap();
+        assert!(!backend.state(&music).muted);
+    }
+
+    #[test]
+    fn restore_keeps_sessions_the_user_had_muted() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.set_mute(&game, true).unwrap();
+        let manager = manager_with(&backend);
+
+        manager.update_mute_status(&config(&["game.exe"], false), true, 0).unwrap();
+        manager.restore_saved_states().unwrap();
+        assert!(backend.state(&game).muted);
+    }
+
+    #[test]
+    fn focus_returns_sessions_to_their_original_state() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.set_mute(&game, true).unwrap();
+        let music = backend.add_session("music.exe", 200);
+        let manager = manager_with(&backend);
+        let config = config(&["game.exe", "music.exe"], false);
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+        manager.update_mute_status(&config, true, 100).unwrap();
+        manager.update_mute_status(&config, true, 200).unwrap();
+        assert!(backend.state(&game).muted);
+        assert!(!backend.state(&music).muted);
+    }
+
+    #[test]
+    fn untouched_sessions_are_left_alone() {
+        let backend = Arc::new(FakeBackend::new());
+        let music = backend.add_session("music.exe", 200);
+        backend.set_mute(&music, true).unwrap();
+        let manager = manager_with(&backend);
+
+        // 不在黑名单中的会话即使被用户静音也不会被取消静音
+        manager.update_mute_status(&config(&["game.exe"], false), true, 0).unwrap();
+        manager.update_mute_status(&config(&["game.exe"], false), false, 0).unwrap();
+        assert!(backend.state(&music).muted);
+    }
+
+    #[test]
+    fn restore_leaves_volume_changed_by_the_user() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let manager = manager_with(&backend);
+
+        manager.update_mute_status(&config(&["game.exe"], false), true, 0).unwrap();
+        backend.set_volume(&game, 0.6).unwrap();
+        manager.restore_saved_states().unwrap();
+        assert_eq!(backend.state(&game), SessionState { muted: false, volume: 0.6 });
+    }
+
+    #[test]
+    fn same_process_on_two_devices_restores_each_session() {
+        let backend = Arc::new(FakeBackend::new());
+        let speakers = backend.add_session_on("speakers", "game.exe", 100);
+        let headset = backend.add_session_on("headset", "game.exe", 100);
+        backend.set_mute(&headset, true).unwrap();
+        let manager = manager_with(&backend);
+
+        manager.update_mute_status(&config(&["game.exe"], false), true, 0).unwrap();
+        assert!(backend.state(&speakers).muted && backend.state(&headset).muted);
+
+        manager.restore_saved_states().unwrap();
+        assert!(!backend.state(&speakers).muted);
+        assert!(backend.state(&headset).muted);
+    }
+
+    #[test]
+    fn restore_survives_the_device_disappearing_and_coming_back() {
+        let backend = Arc::new(FakeBackend::new());
+        let before = backend.add_session_on("headset", "game.exe", 100);
+        let manager = manager_with(&backend);
+        let config = config(&["game.exe"], false);
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+
+        // 耳机拔出：会话消失，期间的同步不能丢掉原始状态
+        backend.remove_session(&before);
+        manager.update_mute_status(&config, true, 0).unwrap();
+
+        // 重新插入后系统以新的会话 ID 重建会话，并沿用了我们设置的静音
+        let after = backend.add_session_on("headset", "game.exe", 100);
+        backend.set_mute(&after, true).unwrap();
+        manager.update_mute_status(&config, true, 0).unwrap();
+
+        manager.restore_saved_states().unwrap();
+        assert!(!backend.state(&after).muted);
+    }
+
+    #[test]
+    fn sibling_sessions_do_not_share_saved_state() {
+        let backend = Arc::new(FakeBackend::new());
+        let first = backend.add_session_on("speakers", "browser.exe", 100);
+        backend.set_mute(&first, true).unwrap();
+        let manager = manager_with(&backend);
+        let config = config(&["browser.exe"], false);
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+        let second = backend.add_session_on("speakers", "browser.exe", 100);
+        manager.update_mute_status(&config, true, 0).unwrap();
+
+        manager.restore_saved_states().unwrap();
+        assert!(backend.state(&first).muted);
+        assert!(!backend.state(&second).muted);
+    }
+
+    #[test]
+    fn steady_state_does_not_touch_the_backend() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.add_session("music.exe", 200);
+        let manager = manager_with(&backend);
+        let config = config(&["game.exe"], false);
+
+        manager.update_mute_status(&config, true, 200).unwrap();
+        assert_eq!(backend.mute_calls(), 1);
+        for _ in 0..10 {
+            manager.update_mute_status(&config, true, 200).unwrap();
+        }
+        assert_eq!(backend.mute_calls(), 1);
+
+        // 期望状态变化时才再次调用后端
+        manager.update_mute_status(&config, true, 100).unwrap();
+        assert_eq!(backend.mute_calls(), 2);
+        assert!(!backend.state(&game).muted);
+    }
+
+    #[test]
+    fn unreported_external_unmute_is_reapplied() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let manager = manager_with(&backend);
+        let config = config(&["game.exe"], false);
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+        // 没有监听外部修改，只能读取实际状态发现会话被改回
+        backend.set_mute(&game, false).unwrap();
+        manager.update_mute_status(&config, true, 0).unwrap();
+        assert!(backend.state(&game).muted);
+    }
+
+    #[test]
+    fn reported_external_change_invalidates_the_applied_state() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let manager = manager_with(&backend);
+        let config = config(&["game.exe"], false);
+        assert!(manager.watch_external_changes(Arc::new(|_| {})).unwrap());
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+        backend.user_set_mute(&game, false);
+        manager.forget_applied(&game);
+        manager.update_mute_status(&config, true, 0).unwrap();
+        assert!(backend.state(&game).muted);
+        assert_eq!(backend.mute_calls(), 2);
+    }
+
+    #[test]
+    fn instance_rule_does_not_follow_a_reused_pid() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        backend.set_process_start(&game, Some(5_000));
+        let manager = manager_with(&backend);
+
+        let mut config = config(&[], false);
+        let (entry, options) = decision::instance_entry(&manager.get_active_sessions().unwrap()[0]);
+        config.list.insert(entry.clone());
+        config.set_options(&entry, options);
+        manager.update_mute_status(&config, true, 0).unwrap();
+        assert!(backend.state(&game).muted);
+
+        // 游戏退出后同名的新进程恰好拿到了同一个 PID
+        backend.remove_session(&game);
+        let restarted = backend.add_session("game.exe", 100);
+        backend.set_process_start(&restarted, Some(9_000));
+        manager.update_mute_status(&config, true, 0).unwrap();
+        assert!(!backend.state(&restarted).muted);
+    }
+
+    #[test]
+    fn display_names_number_duplicate_processes() {
+        let backend = Arc::new(FakeBackend::new());
+        backend.add_session("game.exe", 100);
+        backend.add_session("game.exe", 101);
+        backend.add_session("music.exe", 200);
+        let manager = manager_with(&backend);
+
+        let names: Vec<String> = manager.get_active_sessions().unwrap().into_iter().map(|s| s.display_name).collect();
+        assert_eq!(names, ["game.exe (1)", "game.exe (2)", "music.exe"]);
+    }
+
+    struct StartTimes(HashMap<u32, u64>);
+
+    impl ProcessInfoProvider for StartTimes {
+        fn process_info(&self, pid: u32) -> Option<crate::process::ProcessInfo> {
+            let start = self.0.get(&pid)?;
+            Some(crate::process::ProcessInfo {
+                pid,
+                name: String::new(),
+                exe_path: None,
+                cmdline: Vec::new(),
+                parent_pid: None,
+                start_time: Some(std::time::UNIX_EPOCH + std::time::Duration::from_millis(*start)),
+                kind: crate::process::ProcessKind::Normal,
+                company_name: None,
+                product_name: None,
+            })
+        }
+    }
+
+    /// 测试用的日志，所在的临时目录在离开作用域时删除
+    struct TempJournal {
+        journal: Journal,
+        dir: std::path::PathBuf,
+    }
+
+    impl std::ops::Deref for TempJournal {
+        type Target = Journal;
+
+        fn deref(&self) -> &Journal {
+            &self.journal
+        }
+    }
+
+    impl Drop for TempJournal {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    fn journal(name: &str) -> TempJournal {
+        let dir = std::env::temp_dir().join(format!("silencer-audio-journal-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        TempJournal { journal: Journal::new(dir.join("journal.json")), dir }
+    }
+
+    fn journaled(backend: &Arc<FakeBackend>, journal: &Journal, starts: &[(u32, u64)]) -> AudioManager {
+        manager_with(backend)
+            .with_process_info(Box::new(StartTimes(starts.iter().copied().collect())))
+            .with_journal(Journal::new(journal.path()))
+    }
+
+    #[test]
+    fn journal_tracks_saved_states() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let journal = journal("tracks");
+        let manager = journaled(&backend, &journal, &[(100, 5_000)]);
+        let config = config(&["game.exe"], false);
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+        let entries = journal.load();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].session_id, game);
+        assert_eq!(entries[0].process_start_ms, Some(5_000));
+        assert!(!entries[0].muted);
+
+        // 回到前台恢复后日志清空
+        manager.update_mute_status(&config, true, 100).unwrap();
+        assert!(journal.load().is_empty());
+        assert!(!journal.path().exists());
+    }
+
+    #[test]
+    fn startup_repairs_sessions_left_muted_by_a_crash() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let music = backend.add_session("music.exe", 200);
+        backend.set_mute(&music, true).unwrap();
+        let journal = journal("crash");
+        let config = config(&["game.exe"], false);
+
+        let crashed = journaled(&backend, &journal, &[(100, 5_000), (200, 6_000)]);
+        crashed.update_mute_status(&config, true, 0).unwrap();
+        assert!(backend.state(&game).muted);
+        // 模拟崩溃：不恢复直接丢弃
+        drop(crashed);
+
+        let manager = journaled(&backend, &journal, &[(100, 5_000), (200, 6_000)]);
+        let repairs = manager.repair_from_journal().unwrap();
+        assert!(!backend.state(&game).muted);
+        assert!(backend.state(&music).muted);
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].process_name, "game.exe");
+        assert_eq!(repairs[0].pid, 100);
+        assert!(!repairs[0].restored.muted);
+        assert!(!journal.path().exists());
+
+        // 已修复的日志不会再次生效
+        assert!(manager.repair_from_journal().unwrap().is_empty());
+    }
+
+    #[test]
+    fn startup_repair_does_not_touch_a_reused_pid() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let journal = journal("reused");
+        let config = config(&["game.exe"], false);
+
+        let crashed = journaled(&backend, &journal, &[(100, 5_000)]);
+        crashed.update_mute_status(&config, true, 0).unwrap();
+        drop(crashed);
+
+        // 同一 PID 现在属于另一个启动时间不同的进程，且用户自己静音了它
+        let manager = journaled(&backend, &journal, &[(100, 9_000)]);
+        let other = backend.add_session("tool.exe", 100);
+        backend.remove_session(&game);
+        backend.set_mute(&other, true).unwrap();
+
+        assert!(manager.repair_from_journal().unwrap().is_empty());
+        assert!(backend.state(&other).muted);
+        assert!(!journal.path().exists());
+    }
+
+    #[test]
+    fn startup_repair_unmutes_a_restarted_process() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let journal = journal("restarted");
+        let config = config(&["game.exe"], false);
+
+        let crashed = journaled(&backend, &journal, &[(100, 5_000)]);
+        crashed.update_mute_status(&config, true, 0).unwrap();
+        drop(crashed);
+
+        // 游戏重启后系统沿用了上次的静音状态
+        backend.remove_session(&game);
+        let restarted = backend.add_session("game.exe", 300);
+        backend.set_mute(&restarted, true).unwrap();
+
+        let manager = journaled(&backend, &journal, &[(300, 8_000)]);
+        let repairs = manager.repair_from_journal().unwrap();
+        assert!(!backend.state(&restarted).muted);
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].pid, 300);
+    }
+
+    #[test]
+    fn startup_repair_keeps_states_the_user_set() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 100);
+        let journal = journal("user-set");
+        let config = with_action(config(&["game.exe"], false), "game.exe", BackgroundAction::Duck(50));
+
+        let crashed = journaled(&backend, &journal, &[(100, 5_000)]);
+        crashed.update_mute_status(&config, true, 0).unwrap();
+        drop(crashed);
+
+        backend.remove_session(&game);
+        // 比原进程更早启动的实例不是重启后的进程，即使状态相同也不处理
+        let older = backend.add_session("game.exe", 50);
+        backend.set_volume(&older, 0.5).unwrap();
+        // 重启后用户自己静音了这个实例
+        let muted_by_user = backend.add_session("game.exe", 300);
+        backend.set_mute(&muted_by_user, true).unwrap();
+        let inherited = backend.add_session("game.exe", 400);
+        backend.set_volume(&inherited, 0.5).unwrap();
+
+        let manager = journaled(&backend, &journal, &[(50, 1_000), (300, 8_000), (400, 9_000)]);
+        let repairs = manager.repair_from_journal().unwrap();
+        assert_eq!(repairs.len(), 1);
+        assert_eq!(repairs[0].pid, 400);
+        assert_eq!(backend.state(&inherited), SessionState { muted: false, volume: 1.0 });
+        assert_eq!(backend.state(&muted_by_user), SessionState { muted: true, volume: 1.0 });
+        assert_eq!(backend.state(&older).volume, 0.5);
+    }
+}
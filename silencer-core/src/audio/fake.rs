@@ -0,0 +1,179 @@
+use std::sync::Mutex;
+
+use super::{AudioBackend, AudioError, AudioSessionInfo, ChangeNotifier, ExternalChange, Result, SessionNotifier, SessionState};
+
+/// [`FakeBackend::add_session`] 使用的设备 ID
+pub const DEFAULT_DEVICE: &str = "fake-device";
+
+/// 纯内存的音频后端，用于在没有 Windows 音频栈的环境下运行静音引擎
+pub struct FakeBackend {
+    sessions: Mutex<Vec<(AudioSessionInfo, SessionState)>>,
+    next_id: Mutex<u32>,
+    notify: Mutex<Option<SessionNotifier>>,
+    changes: Mutex<Option<ChangeNotifier>>,
+    mute_calls: Mutex<usize>,
+    // 模拟的故障：修改会话失败，或整个音频服务不可用
+    read_only: Mutex<bool>,
+    unavailable: Mutex<bool>,
+}
+
+impl FakeBackend {
+    pub fn new() -> Self {
+        Self { sessions: Mutex::new(Vec::new()), next_id: Mutex::new(1), notify: Mutex::new(None), changes: Mutex::new(None), mute_calls: Mutex::new(0), read_only: Mutex::new(false), unavailable: Mutex::new(false) }
+    }
+
+    /// 在默认设备上添加一个未静音、满音量的会话，返回其会话 ID
+    pub fn add_session(&self, name: &str, pid: u32) -> String {
+        self.add_session_on(DEFAULT_DEVICE, name, pid)
+    }
+
+    /// 在指定设备上添加一个未静音、满音量的会话，设备名称与设备 ID 相同
+    pub fn add_session_on(&self, device_id: &str, name: &str, pid: u32) -> String {
+        let mut next_id = self.next_id.lock().unwrap();
+        let id = format!("fake-session-{}", *next_id);
+        *next_id += 1;
+
+        let info = AudioSessionInfo {
+            id: id.clone(),
+            name: name.to_string(),
+            pid,
+            process_start_ms: None,
+            parent_pid: None,
+            exe_path: None,
+            company_name: None,
+            product_name: None,
+            window_title: String::new(),
+            display_name: String::new(),
+            device_id: device_id.to_string(),
+            device_name: device_id.to_string(),
+        };
+        self.sessions.lock().unwrap().push((info, SessionState { muted: false, volume: 1.0 }));
+        self.notify();
+        id
+    }
+
+    /// 修改会话所属进程的主窗口标题
+    pub fn set_window_title(&self, id: &str, title: &str) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some((info, _)) = sessions.iter_mut().find(|(info, _)| info.id == id) {
+            info.window_title = title.to_string();
+        }
+    }
+
+    /// 设置会话所属进程的启动时间（Unix 毫秒），用于模拟 PID 被复用
+    pub fn set_process_start(&self, id: &str, process_start_ms: Option<u64>) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some((info, _)) = sessions.iter_mut().find(|(info, _)| info.id == id) {
+            info.process_start_ms = process_start_ms;
+        }
+    }
+
+    pub fn remove_session(&self, id: &str) {
+        self.sessions.lock().unwrap().retain(|(info, _)| info.id != id);
+        self.notify();
+    }
+
+    fn notify(&self) {
+        if let Some(notify) = self.notify.lock().unwrap().as_ref() {
+            notify();
+        }
+    }
+
+    /// 模拟用户在系统音量合成器中修改静音状态，会通知外部修改的监听者
+    pub fn user_set_mute(&self, id: &str, muted: bool) {
+        let volume = self
+            .with_session(id, |state| {
+                state.muted = muted;
+                state.volume
+            })
+            .unwrap();
+        if let Some(notify) = self.changes.lock().unwrap().as_ref() {
+            notify(ExternalChange { session_id: id.to_string(), muted, volume });
+        }
+    }
+
+    /// 当前所有会话，不受 [`FakeBackend::set_unavailable`] 影响
+    pub fn all_sessions(&self) -> Vec<AudioSessionInfo> {
+        self.sessions.lock().unwrap().iter().map(|(info, _)| info.clone()).collect()
+    }
+
+    /// 读取会话当前状态，会话不存在时 panic
+    pub fn state(&self, id: &str) -> SessionState {
+        self.with_session(id, |state| *state).unwrap()
+    }
+
+    /// 开启后修改静音或音量都返回错误，读取不受影响
+    pub fn set_read_only(&self, read_only: bool) {
+        *self.read_only.lock().unwrap() = read_only;
+    }
+
+    /// 开启后所有调用都返回错误，模拟音频服务停止
+    pub fn set_unavailable(&self, unavailable: bool) {
+        *self.unavailable.lock().unwrap() = unavailable;
+    }
+
+    fn check(&self, write: bool) -> Result<()> {
+        if *self.unavailable.lock().unwrap() || (write && *self.read_only.lock().unwrap()) {
+            return Err(AudioError::Unsupported);
+        }
+        Ok(())
+    }
+
+    /// 通过 [`AudioBackend::set_mute`] 修改静音的次数，不包括 [`FakeBackend::user_set_mute`]
+    pub fn mute_calls(&self) -> usize {
+        *self.mute_calls.lock().unwrap()
+    }
+
+    fn with_session<R>(&self, id: &str, f: impl FnOnce(&mut SessionState) -> R) -> Result<R> {
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions
+            .iter_mut()
+            .find(|(info, _)| info.id == id)
+            .map(|(_, state)| f(state))
+            .ok_or_else(|| AudioError::SessionNotFound(id.to_string()))
+    }
+}
+
+impl Default for FakeBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioBackend for FakeBackend {
+    fn sessions(&self) -> Result<Vec<AudioSessionInfo>> {
+        self.check(false)?;
+        Ok(self.all_sessions())
+    }
+
+    fn get_mute(&self, id: &str) -> Result<bool> {
+        self.check(false)?;
+        self.with_session(id, |state| state.muted)
+    }
+
+    fn set_mute(&self, id: &str, muted: bool) -> Result<()> {
+        *self.mute_calls.lock().unwrap() += 1;
+        self.check(true)?;
+        self.with_session(id, |state| state.muted = muted)
+    }
+
+    fn get_volume(&self, id: &str) -> Result<f32> {
+        self.check(false)?;
+        self.with_session(id, |state| state.volume)
+    }
+
+    fn set_volume(&self, id: &str, volume: f32) -> Result<()> {
+        self.check(true)?;
+        self.with_session(id, |state| state.volume = volume)
+    }
+
+    fn watch_sessions(&self, notify: SessionNotifier) -> Result<bool> {
+        *self.notify.lock().unwrap() = Some(notify);
+        Ok(true)
+    }
+
+    fn watch_external_changes(&self, notify: ChangeNotifier) -> Result<bool> {
+        *self.changes.lock().unwrap() = Some(notify);
+        Ok(true)
+    }
+}
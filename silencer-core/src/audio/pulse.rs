@@ -0,0 +1,755 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader};
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use serde::Deserialize;
+
+use super::{AudioBackend, AudioError, AudioSessionInfo, ChangeNotifier, ExternalChange, Result, SessionNotifier};
+use crate::process::ProcessInfoProvider;
+use crate::process::cache::ProcessCache;
+use crate::process::procfs::ProcProcessInfo;
+
+/// PulseAudio 中 100% 音量对应的原始值（PA_VOLUME_NORM）
+const VOLUME_NORM: f32 = 65536.0;
+
+/// 每个 sink-input 最多记住的尚未确认的修改，修改没有产生事件（例如设置为相同的值）时不会无限累积
+const MAX_PENDING: usize = 8;
+
+/// 基于 PulseAudio（或 PipeWire 的 pipewire-pulse）的音频后端，每个 sink-input 对应一个会话。
+/// 通过 `pactl` 命令与服务器通信，因此不需要链接 libpulse。
+pub struct PulseBackend {
+    pactl: String,
+    // `pactl subscribe` 子进程，析构时结束。会话事件与外部修改事件共用同一个子进程
+    subscription: Mutex<Option<Child>>,
+    session_notify: Arc<Mutex<Option<SessionNotifier>>>,
+    change_notify: Arc<Mutex<Option<ChangeNotifier>>>,
+    // 用于把 sink-input 的 `change` 事件区分为我们自己的修改与外部修改
+    changes: Arc<Mutex<ChangeTracker>>,
+    // 会话所属进程的身份，用于区分复用了同一 PID 的进程
+    processes: Mutex<ProcessCache>,
+    // 按索引缓存的播放设备，出现未知设备或收到设备变化事件时重新列出
+    sinks: Arc<Mutex<Option<HashMap<u32, Sink>>>>,
+    // 最近一次枚举到的 sink-input 状态，按索引保存。每次同步都会先枚举会话，
+    // 因此 get_mute/get_volume 直接读取这里，不必为每个会话单独运行 pactl
+    inputs: Mutex<HashMap<String, InputState>>,
+}
+
+/// sink-input 的静音状态与各声道音量（按声道映射的顺序）
+#[derive(Clone, Debug, PartialEq)]
+struct InputState {
+    mute: bool,
+    channels: Vec<u32>,
+    // 最近一次音量不为 0 时各声道相对平均音量的比例，音量降到 0 后仍能按原来的左右平衡恢复
+    balance: Vec<f32>,
+}
+
+impl InputState {
+    fn new(mute: bool, channels: Vec<u32>) -> Self {
+        let balance = balance(&channels).unwrap_or_else(|| vec![1.0; channels.len()]);
+        Self { mute, channels, balance }
+    }
+
+    /// 重新读取到的状态；音量为 0 时保留之前的平衡
+    fn update(&mut self, mute: bool, channels: Vec<u32>) {
+        match balance(&channels) {
+            Some(balance) => self.balance = balance,
+            None if self.balance.len() != channels.len() => self.balance = vec![1.0; channels.len()],
+            None => {}
+        }
+        self.mute = mute;
+        self.channels = channels;
+    }
+
+    /// 各声道音量的平均值，1.0 表示 100%
+    fn volume(&self) -> f32 {
+        if self.channels.is_empty() {
+            return 1.0;
+        }
+        self.channels.iter().map(|&value| value as f32).sum::<f32>() / self.channels.len() as f32 / VOLUME_NORM
+    }
+
+    /// 平均音量为 `volume` 且保持左右平衡的各声道原始值
+    fn scaled(&self, volume: f32) -> Vec<u32> {
+        let volume = volume.max(0.0) * VOLUME_NORM;
+        if self.balance.is_empty() {
+            return vec![volume.round() as u32];
+        }
+        self.balance.iter().map(|ratio| (ratio * volume).round() as u32).collect()
+    }
+}
+
+/// sink-input 的静音状态与各声道原始音量，用于比较事件前后的状态
+#[derive(Clone, Debug, PartialEq)]
+struct Levels {
+    mute: bool,
+    channels: Vec<u32>,
+}
+
+/// 我们自己修改 sink-input 时同样会产生 `change` 事件。记录每个 sink-input 最近确认的状态，
+/// 以及已经发出、尚未在事件中看到的修改，事件后读到的状态与两者都不同时才是外部修改
+#[derive(Default)]
+struct ChangeTracker {
+    inputs: HashMap<String, Tracked>,
+}
+
+#[derive(Default)]
+struct Tracked {
+    confirmed: Option<Levels>,
+    pending: Vec<Levels>,
+}
+
+impl ChangeTracker {
+    /// 枚举时读到的状态，只用于第一次见到的 sink-input
+    fn seed(&mut self, id: &str, levels: Levels) {
+        let tracked = self.inputs.entry(id.to_string()).or_default();
+        if tracked.confirmed.is_none() && tracked.pending.is_empty() {
+            tracked.confirmed = Some(levels);
+        }
+    }
+
+    /// 即将由我们设置的状态
+    fn expect(&mut self, id: &str, levels: Levels) {
+        let pending = &mut self.inputs.entry(id.to_string()).or_default().pending;
+        if pending.len() == MAX_PENDING {
+            pending.remove(0);
+        }
+        pending.push(levels);
+    }
+
+    /// 设置失败，不会再出现对应的事件
+    fn cancel(&mut self, id: &str, levels: &Levels) {
+        if let Some(tracked) = self.inputs.get_mut(id)
+            && let Some(position) = tracked.pending.iter().position(|pending| pending == levels)
+        {
+            tracked.pending.remove(position);
+        }
+    }
+
+    /// 收到 `change` 事件后读到的状态，返回是否为外部修改
+    fn observe(&mut self, id: &str, levels: Levels) -> bool {
+        let tracked = self.inputs.entry(id.to_string()).or_default();
+        if let Some(position) = tracked.pending.iter().position(|pending| *pending == levels) {
+            // 我们自己的修改；更早发出的修改已被它覆盖
+            tracked.pending.drain(..=position);
+            tracked.confirmed = Some(levels);
+            return false;
+        }
+        let external = tracked.confirmed.as_ref().is_some_and(|confirmed| *confirmed != levels);
+        tracked.confirmed = Some(levels);
+        if external {
+            tracked.pending.clear();
+        }
+        external
+    }
+
+    fn forget(&mut self, id: &str) {
+        self.inputs.remove(id);
+    }
+}
+
+/// 各声道相对平均值的比例，平均值为 0 时无法得出
+fn balance(channels: &[u32]) -> Option<Vec<f32>> {
+    let total: u64 = channels.iter().map(|&value| u64::from(value)).sum();
+    if total == 0 {
+        return None;
+    }
+    let average = total as f32 / channels.len() as f32;
+    Some(channels.iter().map(|&value| value as f32 / average).collect())
+}
+
+#[derive(Deserialize)]
+struct SinkInput {
+    index: u32,
+    #[serde(default)]
+    sink: u32,
+    mute: bool,
+    #[serde(default)]
+    volume: HashMap<String, ChannelVolume>,
+    /// 声道名称，以逗号分隔，`volume` 中的声道按此顺序设置
+    #[serde(default)]
+    channel_map: String,
+    #[serde(default)]
+    properties: HashMap<String, String>,
+}
+
+/// 播放设备，sink-input 通过 `sink` 字段引用其索引
+#[derive(Clone, Deserialize)]
+struct Sink {
+    index: u32,
+    name: String,
+    #[serde(default)]
+    description: String,
+}
+
+#[derive(Deserialize)]
+struct ChannelVolume {
+    value: u32,
+}
+
+impl PulseBackend {
+    /// 连接当前用户的 PulseAudio 服务器，服务器不可用时返回错误
+    pub fn new() -> Result<Self> {
+        let backend = Self {
+            pactl: "pactl".to_string(),
+            subscription: Mutex::new(None),
+            session_notify: Arc::new(Mutex::new(None)),
+            change_notify: Arc::new(Mutex::new(None)),
+            changes: Arc::new(Mutex::new(ChangeTracker::default())),
+            processes: Mutex::new(ProcessCache::new(Box::new(ProcProcessInfo::new()))),
+            sinks: Arc::new(Mutex::new(None)),
+            inputs: Mutex::new(HashMap::new()),
+        };
+        backend.pactl(&["info"])?;
+        Ok(backend)
+    }
+
+    fn pactl(&self, args: &[&str]) -> Result<String> {
+        run_pactl(&self.pactl, args)
+    }
+
+    /// 运行 `pactl args` 设置 sink-input 为 `levels`，事件中读到该状态时不视为外部修改
+    fn set_levels(&self, id: &str, args: &[&str], levels: Levels) -> Result<()> {
+        self.changes.lock().unwrap().expect(id, levels.clone());
+        self.pactl(args).inspect_err(|_| self.changes.lock().unwrap().cancel(id, &levels))?;
+        if let Some(state) = self.inputs.lock().unwrap().get_mut(id) {
+            state.update(levels.mute, levels.channels);
+        }
+        Ok(())
+    }
+
+    /// 启动 `pactl subscribe`，已在运行时不重复启动
+    fn subscribe(&self) -> Result<()> {
+        let mut subscription = self.subscription.lock().unwrap();
+        if subscription.is_some() {
+            return Ok(());
+        }
+        let mut child = Command::new(&self.pactl)
+            .arg("subscribe")
+            .env("LC_ALL", "C")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| AudioError::Pulse(format!("无法运行 {} subscribe: {}", self.pactl, e)))?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let subscriber = Subscriber {
+            pactl: self.pactl.clone(),
+            sinks: Arc::clone(&self.sinks),
+            changes: Arc::clone(&self.changes),
+            session_notify: Arc::clone(&self.session_notify),
+            change_notify: Arc::clone(&self.change_notify),
+        };
+        thread::spawn(move || {
+            // 子进程被结束后读到 EOF，线程随之退出
+            for line in BufReader::new(stdout).lines().map_while(std::io::Result::ok) {
+                subscriber.handle(&line);
+            }
+        });
+        *subscription = Some(child);
+        Ok(())
+    }
+
+    /// 列出所有 sink-input，并更新缓存的状态
+    fn sink_inputs(&self) -> Result<Vec<SinkInput>> {
+        let inputs = parse_sink_inputs(&self.pactl(&["--format=json", "list", "sink-inputs"])?)?;
+        let mut states = self.inputs.lock().unwrap();
+        states.retain(|id, _| inputs.iter().any(|input| input.index.to_string() == *id));
+        let mut changes = self.changes.lock().unwrap();
+        changes.inputs.retain(|id, _| inputs.iter().any(|input| input.index.to_string() == *id));
+        for input in &inputs {
+            let channels = input.channels();
+            changes.seed(&input.index.to_string(), input.levels());
+            match states.get_mut(&input.index.to_string()) {
+                Some(state) => state.update(input.mute, channels),
+                None => {
+                    states.insert(input.index.to_string(), InputState::new(input.mute, channels));
+                }
+            }
+        }
+        Ok(inputs)
+    }
+
+    /// 按索引列出所有播放设备。缓存中包含 `inputs` 引用的所有设备时不再运行 pactl
+    fn sinks(&self, inputs: &[SinkInput]) -> Result<HashMap<u32, Sink>> {
+        let mut cached = self.sinks.lock().unwrap();
+        if let Some(sinks) = cached.as_ref()
+            && inputs.iter().all(|input| sinks.contains_key(&input.sink))
+        {
+            return Ok(sinks.clone());
+        }
+        let sinks: HashMap<u32, Sink> = parse_sinks(&self.pactl(&["--format=json", "list", "sinks"])?)?
+            .into_iter()
+            .map(|sink| (sink.index, sink))
+            .collect();
+        *cached = Some(sinks.clone());
+        Ok(sinks)
+    }
+
+    /// 会话最近一次枚举时的状态（见 [`AudioBackend::get_mute`]），尚未枚举到时重新列出一次
+    fn input(&self, id: &str) -> Result<InputState> {
+        if let Some(state) = self.inputs.lock().unwrap().get(id) {
+            return Ok(state.clone());
+        }
+        self.sink_inputs()?;
+        self.inputs.lock().unwrap().get(id).cloned().ok_or_else(|| AudioError::SessionNotFound(id.to_string()))
+    }
+}
+
+/// `pactl subscribe` 读取线程使用的共享状态
+struct Subscriber {
+    pactl: String,
+    sinks: Arc<Mutex<Option<HashMap<u32, Sink>>>>,
+    changes: Arc<Mutex<ChangeTracker>>,
+    session_notify: Arc<Mutex<Option<SessionNotifier>>>,
+    change_notify: Arc<Mutex<Option<ChangeNotifier>>>,
+}
+
+impl Subscriber {
+    fn handle(&self, line: &str) {
+        if is_sink_event(line) {
+            *self.sinks.lock().unwrap() = None;
+        }
+        if let Some(id) = input_event(line, "remove") {
+            self.changes.lock().unwrap().forget(id);
+        }
+        if is_session_event(line)
+            && let Some(notify) = self.session_notify.lock().unwrap().clone()
+        {
+            notify();
+        }
+        if let Some(id) = input_event(line, "change")
+            && let Some(notify) = self.change_notify.lock().unwrap().clone()
+            && let Some(change) = self.external_change(id)
+        {
+            notify(change);
+        }
+    }
+
+    /// 重新读取发生变化的 sink-input，状态不是我们设置的时返回外部修改
+    fn external_change(&self, id: &str) -> Option<ExternalChange> {
+        let output = run_pactl(&self.pactl, &["--format=json", "list", "sink-inputs"]).ok()?;
+        let inputs = parse_sink_inputs(&output).ok()?;
+        let input = inputs.into_iter().find(|input| input.index.to_string() == id)?;
+        let levels = input.levels();
+        if !self.changes.lock().unwrap().observe(id, levels.clone()) {
+            return None;
+        }
+        let volume = InputState::new(levels.mute, levels.channels).volume();
+        Some(ExternalChange { session_id: id.to_string(), muted: input.mute, volume })
+    }
+}
+
+fn run_pactl(pactl: &str, args: &[&str]) -> Result<String> {
+    let output = Command::new(pactl)
+        .args(args)
+        .env("LC_ALL", "C")
+        .output()
+        .map_err(|e| AudioError::Pulse(format!("无法运行 {}: {}", pactl, e)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AudioError::Pulse(stderr.trim().to_string()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `pactl subscribe` 输出的一行为 sink-input 的 `kind` 事件时返回其索引，例如 `Event 'change' on sink-input #42`
+fn input_event<'a>(line: &'a str, kind: &str) -> Option<&'a str> {
+    line.strip_prefix("Event '")?.strip_prefix(kind)?.strip_prefix("' on sink-input #")
+}
+
+/// `pactl subscribe` 输出的一行是否需要重新枚举会话：sink-input 或 sink 的出现与消失
+/// （例如 `Event 'new' on sink-input #42`），以及服务器默认设备的变化。
+/// sink-input 的 `change` 事件由我们自己修改静音/音量时也会触发，忽略以免形成循环
+fn is_session_event(line: &str) -> bool {
+    let added_or_removed = line.starts_with("Event 'new'") || line.starts_with("Event 'remove'");
+    (added_or_removed && (line.contains(" on sink-input #") || line.contains(" on sink #")))
+        || line.starts_with("Event 'change' on server")
+}
+
+/// `pactl subscribe` 输出的一行是否表示播放设备可能已变化（包括设备描述的修改），缓存的设备需要重新列出
+fn is_sink_event(line: &str) -> bool {
+    line.contains(" on sink #") || line.starts_with("Event 'change' on server")
+}
+
+fn parse_sink_inputs(json: &str) -> Result<Vec<SinkInput>> {
+    serde_json::from_str(json).map_err(|e| AudioError::Pulse(format!("无法解析 pactl 输出: {}", e)))
+}
+
+fn parse_sinks(json: &str) -> Result<Vec<Sink>> {
+    serde_json::from_str(json).map_err(|e| AudioError::Pulse(format!("无法解析 pactl 输出: {}", e)))
+}
+
+impl SinkInput {
+    fn pid(&self) -> u32 {
+        self.properties
+            .get("application.process.id")
+            .and_then(|pid| pid.parse().ok())
+            .unwrap_or(0)
+    }
+
+    fn name(&self) -> String {
+        self.properties
+            .get("application.process.binary")
+            .or_else(|| self.properties.get("application.name"))
+            .cloned()
+            .unwrap_or_else(|| ProcProcessInfo::new().process_name(self.pid()))
+    }
+
+    fn levels(&self) -> Levels {
+        Levels { mute: self.mute, channels: self.channels() }
+    }
+
+    /// 按声道映射顺序排列的各声道音量。缺少声道映射时无法确定顺序，退回为一个平均值（对所有声道生效）
+    fn channels(&self) -> Vec<u32> {
+        let ordered: Option<Vec<u32>> = self
+            .channel_map
+            .split(',')
+            .filter(|name| !name.is_empty())
+            .map(|name| self.volume.get(name.trim()).map(|channel| channel.value))
+            .collect();
+        match ordered {
+            Some(channels) if !channels.is_empty() && channels.len() == self.volume.len() => channels,
+            _ if self.volume.is_empty() => Vec::new(),
+            _ => {
+                let total: u64 = self.volume.values().map(|channel| u64::from(channel.value)).sum();
+                vec![(total / self.volume.len() as u64) as u32]
+            }
+        }
+    }
+
+    /// 设备 ID 使用 sink 名称（重启后保持不变），设备名称使用其描述
+    fn to_session_info(&self, sinks: &HashMap<u32, Sink>) -> AudioSessionInfo {
+        let (device_id, device_name) = match sinks.get(&self.sink) {
+            Some(sink) if !sink.description.is_empty() => (sink.name.clone(), sink.description.clone()),
+            Some(sink) => (sink.name.clone(), sink.name.clone()),
+            None => (self.sink.to_string(), format!("设备 #{}", self.sink)),
+        };
+        AudioSessionInfo {
+            id: self.index.to_string(),
+            name: self.name(),
+            pid: self.pid(),
+            process_start_ms: None,
+            parent_pid: None,
+            exe_path: None,
+            company_name: None,
+            product_name: None,
+            window_title: String::new(),
+            display_name: String::new(),
+            device_id,
+            device_name,
+        }
+    }
+}
+
+impl AudioBackend for PulseBackend {
+    fn sessions(&self) -> Result<Vec<AudioSessionInfo>> {
+        let inputs = self.sink_inputs()?;
+        let sinks = self.sinks(&inputs)?;
+        let mut processes = self.processes.lock().unwrap();
+        let sessions: Vec<AudioSessionInfo> = inputs
+            .iter()
+            .filter(|input| input.pid() != 0)
+            .map(|input| {
+                let mut session = input.to_session_info(&sinks);
+                if let Some(process) = processes.lookup(session.pid) {
+                    session.set_process(process);
+                }
+                session
+            })
+            .collect();
+        processes.expire(|pid| sessions.iter().any(|session| session.pid == pid));
+        Ok(sessions)
+    }
+
+    fn get_mute(&self, id: &str) -> Result<bool> {
+        Ok(self.input(id)?.mute)
+    }
+
+    fn set_mute(&self, id: &str, muted: bool) -> Result<()> {
+        let channels = self.input(id)?.channels;
+        self.set_levels(id, &["set-sink-input-mute", id, if muted { "1" } else { "0" }], Levels { mute: muted, channels })
+    }
+
+    fn get_volume(&self, id: &str) -> Result<f32> {
+        Ok(self.input(id)?.volume())
+    }
+
+    /// 按比例调整各声道，保持用户设置的左右平衡
+    fn set_volume(&self, id: &str, volume: f32) -> Result<()> {
+        let state = self.input(id)?;
+        let channels = state.scaled(volume);
+        let values: Vec<String> = channels.iter().map(u32::to_string).collect();
+        let mut args = vec!["set-sink-input-volume", id];
+        args.extend(values.iter().map(String::as_str));
+        self.set_levels(id, &args, Levels { mute: state.mute, channels })
+    }
+
+    fn watch_sessions(&self, notify: SessionNotifier) -> Result<bool> {
+        *self.session_notify.lock().unwrap() = Some(notify);
+        self.subscribe()?;
+        Ok(true)
+    }
+
+    /// sink-input 的 `change` 事件不区分修改者，事件后读到的状态不是我们设置的才上报
+    fn watch_external_changes(&self, notify: ChangeNotifier) -> Result<bool> {
+        *self.change_notify.lock().unwrap() = Some(notify);
+        self.subscribe()?;
+        Ok(true)
+    }
+}
+
+impl Drop for PulseBackend {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.subscription.lock().unwrap().take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SINK_INPUTS: &str = r#"[
+        {
+            "index": 42,
+            "driver": "protocol-native.c",
+            "sink": 1,
+            "corked": false,
+            "mute": false,
+            "volume": {
+                "front-left": {"value": 65536, "value_percent": "100%", "db": "0.00 dB"},
+                "front-right": {"value": 32768, "value_percent": "50%", "db": "-18.06 dB"}
+            },
+            "channel_map": "front-left,front-right",
+            "balance": -0.50,
+            "properties": {
+                "media.name": "Playback Stream",
+                "application.name": "Firefox",
+                "application.process.id": "4242",
+                "application.process.binary": "firefox"
+            }
+        },
+        {
+            "index": 43,
+            "mute": true,
+            "volume": {"mono": {"value": 0, "value_percent": "0%", "db": "-inf dB"}},
+            "channel_map": "mono",
+            "properties": {
+                "application.name": "pacat",
+                "application.process.id": "77"
+            }
+        },
+        {
+            "index": 44,
+            "mute": false,
+            "volume": {},
+            "properties": {"media.name": "loopback"}
+        }
+    ]"#;
+
+    const SINKS: &str = r#"[
+        {"index": 0, "name": "alsa_output.pci-0000_00_1f.3.analog-stereo", "description": "Built-in Audio Analog Stereo", "mute": false},
+        {"index": 1, "name": "bluez_output.00_11_22_33_44_55.1", "description": "Headset", "mute": false}
+    ]"#;
+
+    fn sinks() -> HashMap<u32, Sink> {
+        parse_sinks(SINKS).unwrap().into_iter().map(|sink| (sink.index, sink)).collect()
+    }
+
+    #[test]
+    fn parses_sink_inputs_into_sessions() {
+        let inputs = parse_sink_inputs(SINK_INPUTS).unwrap();
+        let sessions: Vec<AudioSessionInfo> = inputs.iter().map(|input| input.to_session_info(&sinks())).collect();
+
+        assert_eq!(sessions[0].id, "42");
+        assert_eq!(sessions[0].pid, 4242);
+        assert_eq!(sessions[0].name, "firefox");
+        // 没有 application.process.binary 时退回到 application.name
+        assert_eq!(sessions[1].name, "pacat");
+        // 没有 PID 的 sink-input（例如 loopback）在 sessions() 中被跳过
+        assert_eq!(sessions[2].pid, 0);
+    }
+
+    #[test]
+    fn sessions_carry_their_sink() {
+        let inputs = parse_sink_inputs(SINK_INPUTS).unwrap();
+        let sessions: Vec<AudioSessionInfo> = inputs.iter().map(|input| input.to_session_info(&sinks())).collect();
+
+        assert_eq!(sessions[0].device_id, "bluez_output.00_11_22_33_44_55.1");
+        assert_eq!(sessions[0].device_name, "Headset");
+        // 缺少 sink 字段时视为 0 号设备
+        assert_eq!(sessions[1].device_name, "Built-in Audio Analog Stereo");
+
+        // 设备在两次查询之间消失
+        let session = inputs[0].to_session_info(&HashMap::new());
+        assert_eq!(session.device_id, "1");
+        assert_eq!(session.device_name, "设备 #1");
+    }
+
+    fn state(input: &SinkInput) -> InputState {
+        InputState::new(input.mute, input.channels())
+    }
+
+    #[test]
+    fn reads_mute_and_average_volume() {
+        let inputs = parse_sink_inputs(SINK_INPUTS).unwrap();
+
+        assert!(!inputs[0].mute);
+        assert_eq!(inputs[0].channels(), [65536, 32768]);
+        assert!((state(&inputs[0]).volume() - 0.75).abs() < f32::EPSILON);
+        assert!(inputs[1].mute);
+        assert_eq!(state(&inputs[1]).volume(), 0.0);
+        assert_eq!(state(&inputs[2]).volume(), 1.0);
+    }
+
+    #[test]
+    fn volume_changes_keep_the_channel_balance() {
+        let inputs = parse_sink_inputs(SINK_INPUTS).unwrap();
+        let mut state = state(&inputs[0]);
+        assert_eq!(state.scaled(0.75), [65536, 32768]);
+        assert_eq!(state.scaled(0.375), [32768, 16384]);
+
+        // 渐变到 0 后恢复原音量时仍保持平衡
+        state.update(false, state.scaled(0.0));
+        assert_eq!(state.channels, [0, 0]);
+        assert_eq!(state.scaled(0.75), [65536, 32768]);
+    }
+
+    #[test]
+    fn missing_channel_map_falls_back_to_the_average() {
+        let mut inputs = parse_sink_inputs(SINK_INPUTS).unwrap();
+        inputs[0].channel_map.clear();
+        assert_eq!(inputs[0].channels(), [49152]);
+        assert_eq!(state(&inputs[0]).scaled(0.5), [32768]);
+    }
+
+    #[test]
+    fn recognizes_session_events() {
+        assert!(is_session_event("Event 'new' on sink-input #42"));
+        assert!(is_session_event("Event 'remove' on sink-input #42"));
+        assert!(!is_session_event("Event 'change' on sink-input #42"));
+        assert!(!is_session_event("Event 'new' on source-output #7"));
+        assert!(is_session_event("Event 'new' on sink #3"));
+        assert!(is_session_event("Event 'remove' on sink #3"));
+        assert!(!is_session_event("Event 'change' on sink #3"));
+        // 默认设备切换
+        assert!(is_session_event("Event 'change' on server #-1"));
+    }
+
+    #[test]
+    fn sink_changes_invalidate_cached_devices() {
+        assert!(is_sink_event("Event 'change' on sink #3"));
+        assert!(is_sink_event("Event 'remove' on sink #3"));
+        assert!(is_sink_event("Event 'change' on server #-1"));
+        assert!(!is_sink_event("Event 'change' on sink-input #42"));
+    }
+
+    #[test]
+    fn parses_sink_input_events() {
+        assert_eq!(input_event("Event 'change' on sink-input #42", "change"), Some("42"));
+        assert_eq!(input_event("Event 'remove' on sink-input #42", "change"), None);
+        assert_eq!(input_event("Event 'change' on sink #3", "change"), None);
+    }
+
+    fn levels(mute: bool, channels: &[u32]) -> Levels {
+        Levels { mute, channels: channels.to_vec() }
+    }
+
+    #[test]
+    fn own_changes_are_not_reported_as_external() {
+        let mut changes = ChangeTracker::default();
+        changes.seed("42", levels(false, &[65536, 32768]));
+        // 先降低音量再静音，两个事件都可能读到最终状态
+        changes.expect("42", levels(false, &[32768, 16384]));
+        changes.expect("42", levels(true, &[32768, 16384]));
+        assert!(!changes.observe("42", levels(true, &[32768, 16384])));
+        assert!(!changes.observe("42", levels(true, &[32768, 16384])));
+
+        // 用户在音量合成器中取消静音
+        assert!(changes.observe("42", levels(false, &[32768, 16384])));
+        // 之后的无关事件（例如暂停播放）不改变状态
+        assert!(!changes.observe("42", levels(false, &[32768, 16384])));
+    }
+
+    #[test]
+    fn failed_changes_do_not_hide_external_ones() {
+        let mut changes = ChangeTracker::default();
+        changes.seed("42", levels(false, &[65536]));
+        changes.expect("42", levels(true, &[65536]));
+        changes.cancel("42", &levels(true, &[65536]));
+        assert!(changes.observe("42", levels(true, &[65536])));
+        // 枚举不会覆盖已确认的状态
+        changes.seed("42", levels(false, &[65536]));
+        assert!(!changes.observe("42", levels(true, &[65536])));
+    }
+
+    #[test]
+    fn rejects_malformed_output() {
+        assert!(parse_sink_inputs("Sink Input #42").is_err());
+    }
+
+    /// 需要本地 PulseAudio 服务器，例如：
+    ///
+    /// ```sh
+    /// pulseaudio --daemonize --exit-idle-time=-1
+    /// cargo test -- --ignored pulse
+    /// ```
+    ///
+    /// 测试会自行加载一个 null sink 并在其上用 pacat 播放静音数据，不需要真实声卡。
+    #[test]
+    #[ignore = "需要运行中的 PulseAudio 服务器"]
+    fn mutes_and_restores_a_real_sink_input() {
+        use crate::audio::AudioManager;
+        use crate::config::Config;
+        use std::collections::HashSet;
+        use std::fs::File;
+        use std::process::Stdio;
+        use std::time::{Duration, Instant};
+
+        let backend = PulseBackend::new().expect("PulseAudio 服务器不可用");
+        let module = backend
+            .pactl(&["load-module", "module-null-sink", "sink_name=silencer_test"])
+            .unwrap();
+        let mut player = Command::new("pacat")
+            .args(["--device=silencer_test", "--client-name=silencer-test"])
+            .stdin(Stdio::from(File::open("/dev/zero").unwrap()))
+            .spawn()
+            .expect("无法启动 pacat");
+
+        let pid = player.id();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let session = loop {
+            if let Some(s) = backend.sessions().unwrap().into_iter().find(|s| s.pid == pid) {
+                break s;
+            }
+            assert!(Instant::now() < deadline, "pacat 的 sink-input 没有出现");
+            std::thread::sleep(Duration::from_millis(50));
+        };
+        assert!(!backend.get_mute(&session.id).unwrap());
+
+        // 另一个后端读取服务器上的实际状态；读取返回的是枚举时的快照，每次检查前重新枚举
+        let probe = PulseBackend::new().unwrap();
+        let muted = || {
+            probe.sessions().unwrap();
+            probe.get_mute(&session.id).unwrap()
+        };
+        let manager = AudioManager::with_backend(Box::new(backend));
+        let config = Config { list: HashSet::from([session.name.clone()]), ..Default::default() };
+        manager.update_mute_status(&config, true, 0).unwrap();
+        assert!(muted());
+
+        manager.update_mute_status(&config, true, pid).unwrap();
+        assert!(!muted());
+
+        manager.update_mute_status(&config, true, 0).unwrap();
+        manager.restore_saved_states().unwrap();
+        assert!(!muted());
+
+        let _ = player.kill();
+        let _ = player.wait();
+        let _ = probe.pactl(&["unload-module", module.trim()]);
+    }
+}
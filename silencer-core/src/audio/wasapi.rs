@@ -0,0 +1,454 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use windows::core::*;
+use windows::Win32::Devices::FunctionDiscovery::PKEY_Device_FriendlyName;
+use windows::Win32::Media::Audio::*;
+use windows::Win32::System::Com::*;
+use windows::Win32::UI::Shell::PropertiesSystem::PROPERTYKEY;
+
+use super::{AudioBackend, AudioError, AudioSessionInfo, ChangeNotifier, ExternalChange, SessionNotifier};
+use crate::process::cache::ProcessCache;
+use crate::process::win32::Win32ProcessInfo;
+use crate::utils;
+
+/// 新会话创建时由音频服务在其工作线程上回调
+#[implement(IAudioSessionNotification)]
+struct SessionCreatedListener {
+    notify: SessionNotifier,
+}
+
+impl IAudioSessionNotification_Impl for SessionCreatedListener_Impl {
+    fn OnSessionCreated(&self, _newsession: Option<&IAudioSessionControl>) -> Result<()> {
+        (self.notify)();
+        Ok(())
+    }
+}
+
+/// 我们修改静音/音量时传给 WASAPI 的事件上下文，用于在音量变化回调中区分自己的修改与用户在混音器中的修改
+const EVENT_CONTEXT: GUID = GUID::from_u128(0x5f1c_2a7e_8d3b_4c69_a0e4_73b2_19d8_c6f1);
+
+// 外部修改的通知函数，可能在开始监听会话之后才设置，因此由所有会话监听器共享
+type ChangeSlot = Arc<Mutex<Option<ChangeNotifier>>>;
+
+/// 单个会话的事件：状态变化与断开时刷新会话；音量变化只在不是由我们触发时作为外部修改上报，避免形成循环
+#[implement(IAudioSessionEvents)]
+struct SessionEventsListener {
+    notify: SessionNotifier,
+    session_id: String,
+    changes: ChangeSlot,
+}
+
+impl IAudioSessionEvents_Impl for SessionEventsListener_Impl {
+    fn OnDisplayNameChanged(&self, _newdisplayname: &PCWSTR, _eventcontext: *const GUID) -> Result<()> {
+        Ok(())
+    }
+
+    fn OnIconPathChanged(&self, _newiconpath: &PCWSTR, _eventcontext: *const GUID) -> Result<()> {
+        Ok(())
+    }
+
+    fn OnSimpleVolumeChanged(&self, newvolume: f32, newmute: windows::Win32::Foundation::BOOL, eventcontext: *const GUID) -> Result<()> {
+        if !eventcontext.is_null() && unsafe { *eventcontext } == EVENT_CONTEXT {
+            return Ok(());
+        }
+        if let Some(notify) = self.changes.lock().unwrap().as_ref() {
+            notify(ExternalChange { session_id: self.session_id.clone(), muted: newmute.as_bool(), volume: newvolume });
+        }
+        Ok(())
+    }
+
+    fn OnChannelVolumeChanged(&self, _channelcount: u32, _newchannelvolumearray: *const f32, _changedchannel: u32, _eventcontext: *const GUID) -> Result<()> {
+        Ok(())
+    }
+
+    fn OnGroupingParamChanged(&self, _newgroupingparam: *const GUID, _eventcontext: *const GUID) -> Result<()> {
+        Ok(())
+    }
+
+    fn OnStateChanged(&self, _newstate: AudioSessionState) -> Result<()> {
+        (self.notify)();
+        Ok(())
+    }
+
+    fn OnSessionDisconnected(&self, _disconnectreason: AudioSessionDisconnectReason) -> Result<()> {
+        (self.notify)();
+        Ok(())
+    }
+}
+
+// 设备友好名称的缓存，按设备 ID 索引。设备变化的回调在音频服务的线程上清空它
+type DeviceNames = Arc<Mutex<HashMap<String, String>>>;
+
+/// 播放设备插拔、启用/禁用或默认设备切换时回调
+#[implement(IMMNotificationClient)]
+struct EndpointListener {
+    notify: SessionNotifier,
+    device_names: DeviceNames,
+}
+
+impl EndpointListener {
+    fn devices_changed(&self) {
+        self.device_names.lock().unwrap().clear();
+        (self.notify)();
+    }
+}
+
+impl IMMNotificationClient_Impl for EndpointListener_Impl {
+    fn OnDeviceStateChanged(&self, _pwstrdeviceid: &PCWSTR, _dwnewstate: DEVICE_STATE) -> Result<()> {
+        self.devices_changed();
+        Ok(())
+    }
+
+    fn OnDeviceAdded(&self, _pwstrdeviceid: &PCWSTR) -> Result<()> {
+        self.devices_changed();
+        Ok(())
+    }
+
+    fn OnDeviceRemoved(&self, _pwstrdeviceid: &PCWSTR) -> Result<()> {
+        self.devices_changed();
+        Ok(())
+    }
+
+    fn OnDefaultDeviceChanged(&self, flow: EDataFlow, _role: ERole, _pwstrdefaultdeviceid: &PCWSTR) -> Result<()> {
+        // 跟随默认设备的应用会在新设备上重建会话
+        if flow == eRender {
+            (self.notify)();
+        }
+        Ok(())
+    }
+
+    fn OnPropertyValueChanged(&self, _pwstrdeviceid: &PCWSTR, key: &PROPERTYKEY) -> Result<()> {
+        // 用户在声音设置中重命名了设备
+        if *key == PKEY_Device_FriendlyName {
+            self.devices_changed();
+        }
+        Ok(())
+    }
+}
+
+/// 会话通知的注册状态，注销时需要原样传回注册时的接口
+struct SessionWatch {
+    device_enumerator: IMMDeviceEnumerator,
+    endpoint_listener: IMMNotificationClient,
+    // 每个播放设备的会话管理器及其上注册的新会话监听器，按设备 ID 索引
+    managers: HashMap<String, (IAudioSessionManager2, IAudioSessionNotification)>,
+    notify: SessionNotifier,
+    changes: ChangeSlot,
+    // 已注册 IAudioSessionEvents 的会话，按会话实例 ID 索引
+    registered: HashMap<String, (IAudioSessionControl2, IAudioSessionEvents)>,
+}
+
+impl SessionWatch {
+    /// 在新出现的设备上监听会话创建，并注销已消失的设备
+    fn sync_devices(&mut self, devices: &[(String, IMMDevice)]) {
+        self.managers.retain(|id, (manager, listener)| {
+            let alive = devices.iter().any(|(device_id, _)| device_id == id);
+            if !alive {
+                unsafe {
+                    let _ = manager.UnregisterSessionNotification(&*listener);
+                }
+            }
+            alive
+        });
+        for (id, device) in devices {
+            if self.managers.contains_key(id) {
+                continue;
+            }
+            let registered = unsafe {
+                device.Activate::<IAudioSessionManager2>(CLSCTX_ALL, None).and_then(|manager| {
+                    // 在注册前先枚举一次会话，否则会话管理器不会开始发送创建通知
+                    manager.GetSessionEnumerator()?;
+                    let listener: IAudioSessionNotification = SessionCreatedListener { notify: self.notify.clone() }.into();
+                    manager.RegisterSessionNotification(&listener)?;
+                    Ok((manager, listener))
+                })
+            };
+            if let Ok(registered) = registered {
+                self.managers.insert(id.clone(), registered);
+            }
+        }
+    }
+
+    /// 为新出现的会话注册事件，并注销已消失的会话
+    fn sync(&mut self, cache: &HashMap<String, IAudioSessionControl2>) {
+        self.registered.retain(|id, (control, events)| {
+            let alive = cache.contains_key(id);
+            if !alive {
+                unsafe {
+                    let _ = control.UnregisterAudioSessionNotification(&*events);
+                }
+            }
+            alive
+        });
+        for (id, control) in cache {
+            if self.registered.contains_key(id) {
+                continue;
+            }
+            let events: IAudioSessionEvents =
+                SessionEventsListener { notify: self.notify.clone(), session_id: id.clone(), changes: self.changes.clone() }.into();
+            if unsafe { control.RegisterAudioSessionNotification(&events) }.is_ok() {
+                self.registered.insert(id.clone(), (control.clone(), events));
+            }
+        }
+    }
+}
+
+impl Drop for SessionWatch {
+    fn drop(&mut self) {
+        unsafe {
+            for (control, events) in self.registered.values() {
+                let _ = control.UnregisterAudioSessionNotification(events);
+            }
+            for (manager, listener) in self.managers.values() {
+                let _ = manager.UnregisterSessionNotification(listener);
+            }
+            let _ = self.device_enumerator.UnregisterEndpointNotificationCallback(&self.endpoint_listener);
+        }
+    }
+}
+
+/// 基于 WASAPI 的音频后端，操作所有已启用播放设备上的会话
+pub struct WasapiBackend {
+    device_enumerator: IMMDeviceEnumerator,
+    // 最近一次枚举得到的会话，按会话实例 ID 索引，供后续读写静音/音量使用
+    session_cache: RefCell<HashMap<String, IAudioSessionControl2>>,
+    // 每个播放设备的会话管理器，按设备 ID 索引，避免每次枚举都重新激活
+    managers: RefCell<HashMap<String, IAudioSessionManager2>>,
+    // 会话的进程身份按 (PID, 启动时间) 缓存，每次枚举都核对启动时间以识别复用了 PID 的进程，
+    // 进程的所有会话消失后过期
+    processes: RefCell<ProcessCache>,
+    device_names: DeviceNames,
+    watch: RefCell<Option<SessionWatch>>,
+    changes: ChangeSlot,
+}
+
+impl WasapiBackend {
+    pub fn new() -> Result<Self> {
+        unsafe {
+            // 工作线程没有消息循环，单线程套间收不到会话通知；多线程套间中回调由音频服务的线程直接调用
+            CoInitializeEx(None, COINIT_MULTITHREADED).ok()?;
+            let device_enumerator: IMMDeviceEnumerator = CoCreateInstance(
+                &MMDeviceEnumerator,
+                None,
+                CLSCTX_ALL,
+            )?;
+            Ok(Self {
+                device_enumerator,
+                session_cache: RefCell::new(HashMap::new()),
+                managers: RefCell::new(HashMap::new()),
+                processes: RefCell::new(ProcessCache::new(Box::new(Win32ProcessInfo))),
+                device_names: Arc::new(Mutex::new(HashMap::new())),
+                watch: RefCell::new(None),
+                changes: Arc::new(Mutex::new(None)),
+            })
+        }
+    }
+
+    /// 会话所属进程的主窗口标题。浏览器等程序在没有窗口的子进程中播放声音，此时使用父进程的窗口标题
+    fn session_title(titles: &HashMap<u32, String>, session: &AudioSessionInfo) -> String {
+        titles
+            .get(&session.pid)
+            .or_else(|| session.parent_pid.and_then(|parent| titles.get(&parent)))
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// 所有处于启用状态的播放设备及其 ID。单个设备失败（例如枚举期间被拔出）时跳过，不影响其它设备
+    fn render_devices(&self) -> Result<Vec<(String, IMMDevice)>> {
+        unsafe {
+            let collection = self.device_enumerator.EnumAudioEndpoints(eRender, DEVICE_STATE_ACTIVE)?;
+            let mut devices = Vec::new();
+            for i in 0..collection.GetCount()? {
+                if let Ok(device) = collection.Item(i)
+                    && let Ok(id) = Self::device_id(&device)
+                {
+                    devices.push((id, device));
+                }
+            }
+            Ok(devices)
+        }
+    }
+
+    fn device_id(device: &IMMDevice) -> Result<String> {
+        unsafe {
+            let id = device.GetId()?;
+            let result = id.to_string().unwrap_or_default();
+            CoTaskMemFree(Some(id.0 as *const _));
+            Ok(result)
+        }
+    }
+
+    /// 设备的友好名称（例如“扬声器 (Realtek High Definition Audio)”），读取失败时退回到设备 ID。
+    /// 名称按设备 ID 缓存，不必每次同步都读取属性存储
+    fn device_name(&self, device: &IMMDevice, device_id: &str) -> String {
+        if let Some(name) = self.device_names.lock().unwrap().get(device_id) {
+            return name.clone();
+        }
+        let name = Self::read_device_name(device, device_id);
+        self.device_names.lock().unwrap().insert(device_id.to_string(), name.clone());
+        name
+    }
+
+    fn read_device_name(device: &IMMDevice, device_id: &str) -> String {
+        unsafe {
+            device
+                .OpenPropertyStore(STGM_READ)
+                .and_then(|store| store.GetValue(&PKEY_Device_FriendlyName))
+                .map(|value| value.to_string())
+                .ok()
+                .filter(|name| !name.is_empty())
+                .unwrap_or_else(|| device_id.to_string())
+        }
+    }
+
+    fn enumerate(&self, devices: &[(String, IMMDevice)]) -> Vec<(AudioSessionInfo, IAudioSessionControl2)> {
+        let mut managers = self.managers.borrow_mut();
+        managers.retain(|id, _| devices.iter().any(|(device_id, _)| device_id == id));
+        self.device_names.lock().unwrap().retain(|id, _| devices.iter().any(|(device_id, _)| device_id == id));
+        let mut processes = self.processes.borrow_mut();
+        let mut sessions = Vec::new();
+        for (device_id, device) in devices {
+            let manager = match managers.get(device_id) {
+                Some(manager) => manager.clone(),
+                None => match unsafe { device.Activate::<IAudioSessionManager2>(CLSCTX_ALL, None) } {
+                    Ok(manager) => {
+                        managers.insert(device_id.clone(), manager.clone());
+                        manager
+                    }
+                    Err(_) => continue,
+                },
+            };
+            let device_name = self.device_name(device, device_id);
+            let _ = Self::enumerate_device(&manager, device_id, &device_name, &mut processes, &mut sessions);
+        }
+        // 标题随时可能变化（例如浏览器切换标签页），每次枚举都重新读取
+        let titles = utils::get_window_titles();
+        for (info, _) in &mut sessions {
+            info.window_title = Self::session_title(&titles, info);
+        }
+        processes.expire(|pid| sessions.iter().any(|(info, _)| info.pid == pid));
+        sessions
+    }
+
+    fn enumerate_device(
+        manager: &IAudioSessionManager2,
+        device_id: &str,
+        device_name: &str,
+        processes: &mut ProcessCache,
+        sessions: &mut Vec<(AudioSessionInfo, IAudioSessionControl2)>,
+    ) -> Result<()> {
+        unsafe {
+            let enumerator = manager.GetSessionEnumerator()?;
+            let count = enumerator.GetCount()?;
+
+            for i in 0..count {
+                let session = enumerator.GetSession(i)?;
+                let session2: IAudioSessionControl2 = session.cast()?;
+                let pid = session2.GetProcessId()?;
+
+                if pid == 0 { continue; }
+
+                let instance_id = session2.GetSessionInstanceIdentifier()?;
+                let id = instance_id.to_string().unwrap_or_default();
+                CoTaskMemFree(Some(instance_id.0 as *const _));
+
+                let mut info = AudioSessionInfo {
+                    id,
+                    name: String::new(),
+                    pid,
+                    process_start_ms: None,
+                    parent_pid: None,
+                    exe_path: None,
+                    company_name: None,
+                    product_name: None,
+                    window_title: String::new(),
+                    display_name: String::new(),
+                    device_id: device_id.to_string(),
+                    device_name: device_name.to_string(),
+                };
+                match processes.lookup(pid) {
+                    Some(process) => {
+                        info.name = process.name.clone();
+                        info.set_process(process);
+                    }
+                    None => info.name = utils::get_process_name_by_pid(pid),
+                }
+                sessions.push((info, session2));
+            }
+        }
+        Ok(())
+    }
+
+    fn simple_volume(&self, id: &str) -> super::Result<ISimpleAudioVolume> {
+        if !self.session_cache.borrow().contains_key(id) {
+            // 会话可能是在上次枚举之后才出现的，重新枚举一次
+            self.sessions()?;
+        }
+        let cache = self.session_cache.borrow();
+        let session = cache.get(id).ok_or_else(|| AudioError::SessionNotFound(id.to_string()))?;
+        Ok(session.cast()?)
+    }
+}
+
+impl AudioBackend for WasapiBackend {
+    fn sessions(&self) -> super::Result<Vec<AudioSessionInfo>> {
+        let devices = self.render_devices()?;
+        if let Some(watch) = self.watch.borrow_mut().as_mut() {
+            watch.sync_devices(&devices);
+        }
+        let enumerated = self.enumerate(&devices);
+        let mut cache = self.session_cache.borrow_mut();
+        cache.clear();
+        let mut sessions = Vec::with_capacity(enumerated.len());
+        for (info, control) in enumerated {
+            cache.insert(info.id.clone(), control);
+            sessions.push(info);
+        }
+        if let Some(watch) = self.watch.borrow_mut().as_mut() {
+            watch.sync(&cache);
+        }
+        Ok(sessions)
+    }
+
+    fn get_mute(&self, id: &str) -> super::Result<bool> {
+        unsafe { Ok(self.simple_volume(id)?.GetMute()?.as_bool()) }
+    }
+
+    fn set_mute(&self, id: &str, muted: bool) -> super::Result<()> {
+        unsafe { Ok(self.simple_volume(id)?.SetMute(muted, &EVENT_CONTEXT)?) }
+    }
+
+    fn get_volume(&self, id: &str) -> super::Result<f32> {
+        unsafe { Ok(self.simple_volume(id)?.GetMasterVolume()?) }
+    }
+
+    fn set_volume(&self, id: &str, volume: f32) -> super::Result<()> {
+        unsafe { Ok(self.simple_volume(id)?.SetMasterVolume(volume, &EVENT_CONTEXT)?) }
+    }
+
+    fn watch_sessions(&self, notify: SessionNotifier) -> super::Result<bool> {
+        let endpoint_listener: IMMNotificationClient =
+            EndpointListener { notify: notify.clone(), device_names: self.device_names.clone() }.into();
+        unsafe {
+            self.device_enumerator.RegisterEndpointNotificationCallback(&endpoint_listener)?;
+        }
+        *self.watch.borrow_mut() = Some(SessionWatch {
+            device_enumerator: self.device_enumerator.clone(),
+            endpoint_listener,
+            managers: HashMap::new(),
+            notify,
+            changes: self.changes.clone(),
+            registered: HashMap::new(),
+        });
+        // 为已有的设备与会话注册事件
+        self.sessions()?;
+        Ok(true)
+    }
+
+    fn watch_external_changes(&self, notify: ChangeNotifier) -> super::Result<bool> {
+        *self.changes.lock().unwrap() = Some(notify);
+        // 音量变化事件随会话事件一起注册
+        Ok(self.watch.borrow().is_some())
+    }
+}
@@ -0,0 +1,60 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// 时间来源。引擎与调度器只通过它读取当前时间，测试中可替换为 [`VirtualClock`]
+pub trait Clock: Send {
+    fn now(&self) -> Instant;
+}
+
+/// 读取系统单调时钟
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// 只在显式推进时才前进的虚拟时钟，克隆后共享同一时间
+#[derive(Clone)]
+pub struct VirtualClock {
+    now: Arc<Mutex<Instant>>,
+}
+
+impl VirtualClock {
+    pub fn new() -> Self {
+        Self { now: Arc::new(Mutex::new(Instant::now())) }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
+
+impl Default for VirtualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Instant {
+        *self.now.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn virtual_clock_only_moves_when_advanced() {
+        let clock = VirtualClock::new();
+        let shared = clock.clone();
+        let start = clock.now();
+
+        assert_eq!(clock.now(), start);
+        shared.advance(Duration::from_millis(50));
+        assert_eq!(clock.now(), start + Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,239 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::pattern::{self, PatternError};
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Config {
+    pub list: HashSet<String>,
+    pub is_whitelist: bool,
+    /// 列表条目的附加选项，按条目文本索引；没有设置选项的条目不会出现在这里
+    #[serde(default)]
+    pub rule_options: HashMap<String, RuleOptions>,
+    /// 用户在系统音量合成器中手动修改静音状态时的处理方式
+    #[serde(default)]
+    pub external_change_policy: ExternalChangePolicy,
+    /// 对所有会话生效的前台关系，条目上设置的关系在此基础上追加
+    #[serde(default)]
+    pub foreground_relations: ForegroundRelations,
+    /// 联动分组：组内的程序按分组的方式一起保持有声
+    #[serde(default)]
+    pub groups: Vec<AppGroup>,
+    /// 没有在条目上设置动作的后台会话使用的动作（包括白名单模式下未列出的会话）
+    #[serde(default)]
+    pub background_action: BackgroundAction,
+    /// 会话转到后台、执行动作前的淡出
+    #[serde(default)]
+    pub fade_out: Fade,
+    /// 会话回到前台、恢复原始状态前的淡入
+    #[serde(default)]
+    pub fade_in: Fade,
+}
+
+/// 音量渐变的时长与曲线，时长为 0 时立即切换
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Fade {
+    #[serde(default)]
+    pub duration_ms: u32,
+    #[serde(default)]
+    pub curve: FadeCurve,
+}
+
+/// 渐变曲线：把经过的时间比例映射为音量变化的完成比例
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FadeCurve {
+    #[default]
+    Linear,
+    /// 开始时变化慢、结束时变化快
+    EaseIn,
+    /// 开始时变化快、结束时变化慢
+    EaseOut,
+    /// 两端慢、中间快
+    EaseInOut,
+}
+
+impl FadeCurve {
+    /// `t` 为经过的时间比例，超出 [0, 1] 时按端点处理
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            FadeCurve::Linear => t,
+            FadeCurve::EaseIn => t * t,
+            FadeCurve::EaseOut => 1.0 - (1.0 - t) * (1.0 - t),
+            FadeCurve::EaseInOut => t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+/// 会话需要静音时实际执行的动作。降低音量时以修改前的原始音量为准，恢复时回到原始音量
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum BackgroundAction {
+    /// 静音
+    #[default]
+    Mute,
+    /// 音量降到原始音量的百分之几
+    Duck(u8),
+    /// 音量不超过百分之几，原始音量更低时保持不变
+    Cap(u8),
+}
+
+impl BackgroundAction {
+    /// 执行动作后的音量，静音时返回 None
+    pub fn volume(&self, original: f32) -> Option<f32> {
+        match *self {
+            BackgroundAction::Mute => None,
+            BackgroundAction::Duck(percent) => Some(original * percent.min(100) as f32 / 100.0),
+            BackgroundAction::Cap(percent) => Some(original.min(percent.min(100) as f32 / 100.0)),
+        }
+    }
+
+    /// 两个动作中更安静的一个：静音最安静，其余按百分比比较，相同时取降低音量
+    pub fn quieter(self, other: Self) -> Self {
+        let rank = |action: Self| match action {
+            BackgroundAction::Mute => (0, 0),
+            BackgroundAction::Duck(percent) => (1, percent as u16 * 2),
+            BackgroundAction::Cap(percent) => (1, percent as u16 * 2 + 1),
+        };
+        if rank(other) < rank(self) { other } else { self }
+    }
+}
+
+/// 一组需要一起保持有声的程序，例如 "游戏 + Discord + 音乐播放器"。分组只会让成员保持有声，不会静音任何会话
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct AppGroup {
+    pub name: String,
+    /// 成员条目，格式与列表条目相同（进程名、通配符、正则表达式、path: 等）
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub mode: GroupMode,
+}
+
+/// 分组让成员保持有声的方式
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub enum GroupMode {
+    /// 任一成员位于前台时，所有成员都不静音
+    #[default]
+    AnyFocused,
+    /// 该成员有未被静音的会话或位于前台时，其它成员都不静音
+    FollowMember(String),
+}
+
+impl AppGroup {
+    pub fn new(name: &str) -> Self {
+        Self { name: name.to_string(), ..Default::default() }
+    }
+
+    /// 校验并预编译条目后加入成员，已存在的成员不会重复加入
+    pub fn add_member(&mut self, entry: &str) -> Result<(), PatternError> {
+        pattern::compile(entry)?;
+        if !self.members.iter().any(|member| member == entry) {
+            self.members.push(entry.to_string());
+        }
+        Ok(())
+    }
+
+    /// 移除成员；被跟随的成员移除后回到 "任一成员位于前台" 方式
+    pub fn remove_member(&mut self, entry: &str) {
+        self.members.retain(|member| member != entry);
+        if self.mode == GroupMode::FollowMember(entry.to_string()) {
+            self.mode = GroupMode::AnyFocused;
+        }
+    }
+}
+
+/// 规则与用户的手动修改冲突时如何处理
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExternalChangePolicy {
+    /// 以规则为准，下一次同步时重新应用
+    #[default]
+    Override,
+    /// 该会话暂停执行规则，直到前台窗口切换
+    PauseUntilFocusChange,
+    /// 把手动修改记为新的列表规则
+    AdoptAsRule,
+}
+
+/// 与前台进程有哪些关系的进程也视为位于前台，用于由其它进程播放声音的程序
+/// （例如 Chromium/Electron 的音频服务进程、由启动器拉起的游戏）
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ForegroundRelations {
+    /// 前台进程的子进程及更下层的后代进程
+    #[serde(default)]
+    pub descendants: bool,
+    /// 前台进程的父进程及更上层的祖先进程，例如拉起游戏的启动器
+    #[serde(default)]
+    pub ancestors: bool,
+    /// 与前台进程属于同一进程组（Linux 的进程组；Windows 无法查询其它进程所属的作业对象，不会命中）
+    #[serde(default)]
+    pub same_group: bool,
+}
+
+impl ForegroundRelations {
+    /// 是否启用了任一关系
+    pub fn any(&self) -> bool {
+        self.descendants || self.ancestors || self.same_group
+    }
+
+    /// 两组关系的并集
+    pub fn union(self, other: Self) -> Self {
+        Self {
+            descendants: self.descendants || other.descendants,
+            ancestors: self.ancestors || other.ancestors,
+            same_group: self.same_group || other.same_group,
+        }
+    }
+}
+
+/// 单个列表条目的附加选项
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct RuleOptions {
+    /// 只对该播放设备（设备 ID）上的会话生效，`None` 表示所有设备
+    #[serde(default)]
+    pub device: Option<String>,
+    /// 实例条目（"进程名 [PID]"）所绑定进程的启动时间（Unix 毫秒），PID 被其它进程复用后不再命中
+    #[serde(default)]
+    pub process_start_ms: Option<u64>,
+    /// 命中该条目的会话额外适用的前台关系（仅黑名单模式下有意义，白名单中的会话不会被静音）
+    #[serde(default)]
+    pub foreground_relations: ForegroundRelations,
+    /// 命中该条目的会话需要静音时执行的动作，`None` 表示使用全局设置
+    #[serde(default)]
+    pub action: Option<BackgroundAction>,
+    /// 失去前台后保持有声的秒数，期间切回前台不会静音。从切换后的那次同步开始计时，0 表示立即执行动作
+    #[serde(default)]
+    pub mute_delay_secs: u32,
+}
+
+impl Config {
+    /// 条目的附加选项，未设置时返回默认值
+    pub fn options(&self, entry: &str) -> RuleOptions {
+        self.rule_options.get(entry).cloned().unwrap_or_default()
+    }
+
+    /// 设置条目的附加选项，默认值不会被保存
+    pub fn set_options(&mut self, entry: &str, options: RuleOptions) {
+        if options == RuleOptions::default() {
+            self.rule_options.remove(entry);
+        } else {
+            self.rule_options.insert(entry.to_string(), options);
+        }
+    }
+
+    /// 校验并预编译条目（见 [`crate::pattern::Pattern`]）后加入列表，无效的通配符或正则表达式返回错误
+    pub fn add(&mut self, entry: &str) -> Result<(), PatternError> {
+        pattern::compile(entry)?;
+        self.list.insert(entry.to_string());
+        Ok(())
+    }
+
+    /// 全局或任一条目是否启用了前台关系，启用时判断前台需要查询进程树
+    pub fn uses_foreground_relations(&self) -> bool {
+        self.foreground_relations.any() || self.rule_options.values().any(|options| options.foreground_relations.any())
+    }
+
+    /// 从列表中移除条目及其选项
+    pub fn remove(&mut self, entry: &str) {
+        self.list.remove(entry);
+        self.rule_options.remove(entry);
+    }
+}
@@ -0,0 +1,802 @@
+use std::time::Duration;
+
+use crate::audio::AudioSessionInfo;
+use crate::config::{AppGroup, BackgroundAction, Config, ForegroundRelations, GroupMode, RuleOptions};
+use crate::pattern;
+use crate::process::tree::ProcessTree;
+
+/// 对单个会话的静音决策
+#[derive(Clone, Debug, PartialEq)]
+pub struct MuteDecision {
+    pub id: String,
+    pub pid: u32,
+    pub mute: bool,
+    /// 需要静音时执行的动作
+    pub action: BackgroundAction,
+    /// 刚失去前台的会话延迟多久再执行动作
+    pub mute_delay: Duration,
+}
+
+/// 前台进程，以及判断会话进程与它的关系时使用的进程树。没有进程树时只有前台进程自己的会话位于前台
+#[derive(Clone, Copy)]
+pub struct Foreground<'a> {
+    pub pid: u32,
+    pub tree: Option<&'a ProcessTree<'a>>,
+    /// 前台进程的身份（进程名、路径等），用于判断没有音频会话的分组成员是否位于前台
+    pub process: Option<&'a AudioSessionInfo>,
+}
+
+impl From<u32> for Foreground<'_> {
+    fn from(pid: u32) -> Self {
+        Self { pid, tree: None, process: None }
+    }
+}
+
+/// 判断单个条目是否命中会话：按进程名、通配符或正则表达式（见 [`crate::pattern::Pattern`]），
+/// 或 "进程名 [PID]" 实例条目（精确匹配，绑定了进程启动时间时还要求启动时间一致），并且会话位于条目限定的设备上
+pub fn entry_matches(entry: &str, options: &RuleOptions, session: &AudioSessionInfo) -> bool {
+    let name_matches = pattern::compiled(entry).is_some_and(|pattern| pattern.matches(session))
+        || (entry == format!("{} [{}]", session.name, session.pid)
+            && options.process_start_ms.is_none_or(|start| session.process_start_ms == Some(start)));
+    name_matches && options.device.as_ref().is_none_or(|device| *device == session.device_id)
+}
+
+/// 绑定到会话所属进程的实例条目及其选项，PID 被复用后不会命中新进程
+pub fn instance_entry(session: &AudioSessionInfo) -> (String, RuleOptions) {
+    let options = RuleOptions { process_start_ms: session.process_start_ms, ..Default::default() };
+    (format!("{} [{}]", session.name, session.pid), options)
+}
+
+/// 没有绑定启动时间的实例条目（旧版本保存的 "进程名 [PID]"）及其第一次命中的进程的启动时间，
+/// 应以 [`bind_instance_entry`] 绑定，此后 PID 被其它进程复用时不再命中
+pub fn unbound_instance_entries(config: &Config, sessions: &[AudioSessionInfo]) -> Vec<(String, u64)> {
+    let mut unbound: Vec<(String, u64)> = Vec::new();
+    for session in sessions {
+        let (entry, _) = instance_entry(session);
+        if let Some(start) = session.process_start_ms
+            && config.list.contains(&entry)
+            && config.options(&entry).process_start_ms.is_none()
+            && !unbound.iter().any(|(listed, _)| *listed == entry)
+        {
+            unbound.push((entry, start));
+        }
+    }
+    unbound
+}
+
+/// 把实例条目绑定到进程启动时间。条目已不在列表中或已经绑定时不做修改
+pub fn bind_instance_entry(config: &mut Config, entry: &str, process_start_ms: u64) {
+    let mut options = config.options(entry);
+    if config.list.contains(entry) && options.process_start_ms.is_none() {
+        options.process_start_ms = Some(process_start_ms);
+        config.set_options(entry, options);
+    }
+}
+
+/// 按可执行文件完整路径匹配该会话的条目，路径未知时返回 None
+pub fn path_entry(session: &AudioSessionInfo) -> Option<String> {
+    session.exe_path.as_ref().map(|path| format!("{}{}", pattern::PATH_PREFIX, path))
+}
+
+/// 按发布者匹配该会话的条目：优先使用公司名称，没有时使用产品名称
+pub fn publisher_entry(session: &AudioSessionInfo) -> Option<String> {
+    match (&session.company_name, &session.product_name) {
+        (Some(company), _) => Some(format!("{}{}", pattern::COMPANY_PREFIX, company)),
+        (None, Some(product)) => Some(format!("{}{}", pattern::PRODUCT_PREFIX, product)),
+        (None, None) => None,
+    }
+}
+
+/// 把用户对会话的手动静音修改记为规则，使该会话在后台时保持 `muted`。
+/// 黑名单中的条目表示静音、白名单中的条目表示不静音，因此按模式添加会话的进程名或移除命中它的条目
+pub fn adopt(config: &mut Config, session: &AudioSessionInfo, muted: bool) {
+    let listed = muted != config.is_whitelist;
+    if listed {
+        if !is_in_list(config, session) {
+            config.list.insert(session.name.clone());
+        }
+    } else {
+        let matching: Vec<String> = config
+            .list
+            .iter()
+            .filter(|entry| entry_matches(entry, &config.options(entry), session))
+            .cloned()
+            .collect();
+        for entry in matching {
+            config.remove(&entry);
+        }
+    }
+}
+
+/// 判断会话是否命中列表中的任一条目
+pub fn is_in_list(config: &Config, session: &AudioSessionInfo) -> bool {
+    config.list.iter().any(|entry| {
+        let options = config.rule_options.get(entry).cloned().unwrap_or_default();
+        entry_matches(entry, &options, session)
+    })
+}
+
+/// 会话需要静音时执行的动作：命中的条目上设置了动作时取其中最安静的一个，否则使用全局设置
+pub fn action(config: &Config, session: &AudioSessionInfo) -> BackgroundAction {
+    config
+        .rule_options
+        .iter()
+        .filter(|(entry, options)| options.action.is_some() && config.list.contains(*entry) && entry_matches(entry, options, session))
+        .filter_map(|(_, options)| options.action)
+        .reduce(BackgroundAction::quieter)
+        .unwrap_or(config.background_action)
+}
+
+/// 会话失去前台后的宽限期：命中该会话的条目中最长的一个，没有条目设置时为 0
+pub fn mute_delay(config: &Config, session: &AudioSessionInfo) -> Duration {
+    let secs = config
+        .rule_options
+        .iter()
+        .filter(|(entry, options)| options.mute_delay_secs > 0 && config.list.contains(*entry) && entry_matches(entry, options, session))
+        .map(|(_, options)| options.mute_delay_secs)
+        .max()
+        .unwrap_or(0);
+    Duration::from_secs(secs.into())
+}
+
+/// 会话适用的前台关系：全局设置加上所有命中该会话的条目上设置的关系。
+/// 白名单模式下命中条目的会话本来就不会被静音，条目上的关系不起作用，只有全局设置有效
+pub fn foreground_relations(config: &Config, session: &AudioSessionInfo) -> ForegroundRelations {
+    config.rule_options.iter().fold(config.foreground_relations, |relations, (entry, options)| {
+        if options.foreground_relations.any() && config.list.contains(entry) && entry_matches(entry, options, session) {
+            relations.union(options.foreground_relations)
+        } else {
+            relations
+        }
+    })
+}
+
+/// 判断会话是否位于前台：属于前台进程，或按适用的前台关系与前台进程相关
+pub fn is_foreground<'a>(session: &AudioSessionInfo, config: &Config, foreground: impl Into<Foreground<'a>>) -> bool {
+    let foreground = foreground.into();
+    if session.pid == foreground.pid {
+        return true;
+    }
+    let Some(tree) = foreground.tree else {
+        return false;
+    };
+    if foreground.pid == 0 {
+        return false;
+    }
+    let relations = foreground_relations(config, session);
+    (relations.descendants && tree.is_descendant(session.pid, foreground.pid))
+        || (relations.ancestors && tree.is_descendant(foreground.pid, session.pid))
+        || (relations.same_group && tree.same_group(session.pid, foreground.pid))
+}
+
+pub fn should_mute<'a>(session: &AudioSessionInfo, config: &Config, enabled: bool, foreground: impl Into<Foreground<'a>>) -> bool {
+    if !enabled {
+        return false;
+    }
+
+    let is_foreground = is_foreground(session, config, foreground);
+    let is_in_list = is_in_list(config, session);
+
+    if config.is_whitelist {
+        !is_in_list && !is_foreground
+    } else {
+        is_in_list && !is_foreground
+    }
+}
+
+/// 会话是否命中分组成员条目
+fn member_matches(member: &str, session: &AudioSessionInfo) -> bool {
+    entry_matches(member, &RuleOptions::default(), session)
+}
+
+/// 分组中此时应保持有声的成员条目。`decisions` 为不考虑分组时的静音计划，与 `sessions` 一一对应
+fn audible_members<'g>(
+    group: &'g AppGroup,
+    sessions: &[AudioSessionInfo],
+    decisions: &[MuteDecision],
+    config: &Config,
+    foreground: Foreground,
+) -> Vec<&'g str> {
+    let focused = |member: &str| {
+        foreground.process.is_some_and(|process| member_matches(member, process))
+            || sessions.iter().any(|session| member_matches(member, session) && is_foreground(session, config, foreground))
+    };
+    match &group.mode {
+        GroupMode::AnyFocused => {
+            if group.members.iter().any(|member| focused(member)) {
+                group.members.iter().map(String::as_str).collect()
+            } else {
+                Vec::new()
+            }
+        }
+        GroupMode::FollowMember(leader) => {
+            let audible = focused(leader)
+                || sessions.iter().zip(decisions).any(|(session, decision)| !decision.mute && member_matches(leader, session));
+            if audible {
+                group.members.iter().filter(|member| *member != leader).map(String::as_str).collect()
+            } else {
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// 根据会话快照、配置、运行状态和前台进程计算每个会话的静音计划（顺序与输入一致）。
+/// 先按列表与前台关系决定，再由联动分组让应保持有声的成员取消静音
+pub fn plan<'a>(sessions: &[AudioSessionInfo], config: &Config, enabled: bool, foreground: impl Into<Foreground<'a>>) -> Vec<MuteDecision> {
+    let foreground = foreground.into();
+    let mut decisions: Vec<MuteDecision> = sessions
+        .iter()
+        .map(|session| MuteDecision {
+            id: session.id.clone(),
+            pid: session.pid,
+            mute: should_mute(session, config, enabled, foreground),
+            action: action(config, session),
+            mute_delay: mute_delay(config, session),
+        })
+        .collect();
+    if !enabled || config.groups.is_empty() {
+        return decisions;
+    }
+
+    // 每个分组都按不考虑分组时的计划判断，结果与分组顺序无关
+    let audible: Vec<&str> = config
+        .groups
+        .iter()
+        .flat_map(|group| audible_members(group, sessions, &decisions, config, foreground))
+        .collect();
+    for (session, decision) in sessions.iter().zip(&mut decisions) {
+        if decision.mute && audible.iter().any(|member| member_matches(member, session)) {
+            decision.mute = false;
+        }
+    }
+    decisions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const BLACKLIST: bool = false;
+    const WHITELIST: bool = true;
+
+    fn session(id: &str, name: &str, pid: u32) -> AudioSessionInfo {
+        AudioSessionInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            pid,
+            process_start_ms: None,
+            parent_pid: None,
+            exe_path: None,
+            company_name: None,
+            product_name: None,
+            window_title: String::new(),
+            display_name: String::new(),
+            device_id: "speakers".to_string(),
+            device_name: String::new(),
+        }
+    }
+
+    fn list(items: &[&str]) -> HashSet<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn config(list: &HashSet<String>, is_whitelist: bool) -> Config {
+        Config { list: list.clone(), is_whitelist, ..Default::default() }
+    }
+
+    fn mutes(sessions: &[AudioSessionInfo], list: &HashSet<String>, is_whitelist: bool, foreground_pid: u32) -> Vec<bool> {
+        plan(sessions, &config(list, is_whitelist), true, foreground_pid).into_iter().map(|d| d.mute).collect()
+    }
+
+    #[test]
+    fn blacklist_mutes_only_listed_background_sessions() {
+        let sessions = [session("a", "game.exe", 100), session("b", "music.exe", 200)];
+        let list = list(&["game.exe"]);
+
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 200), [true, false]);
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 100), [false, false]);
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 999), [true, false]);
+    }
+
+    #[test]
+    fn whitelist_mutes_unlisted_background_sessions() {
+        let sessions = [session("a", "game.exe", 100), session("b", "music.exe", 200), session("c", "chat.exe", 300)];
+        let list = list(&["music.exe"]);
+
+        assert_eq!(mutes(&sessions, &list, WHITELIST, 100), [false, false, true]);
+        assert_eq!(mutes(&sessions, &list, WHITELIST, 200), [true, false, true]);
+        assert_eq!(mutes(&sessions, &list, WHITELIST, 999), [true, false, true]);
+    }
+
+    #[test]
+    fn empty_list_in_both_modes() {
+        let sessions = [session("a", "game.exe", 100), session("b", "music.exe", 200)];
+        let list = HashSet::new();
+
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 100), [false, false]);
+        assert_eq!(mutes(&sessions, &list, WHITELIST, 100), [false, true]);
+    }
+
+    #[test]
+    fn disabled_never_mutes() {
+        let sessions = [session("a", "game.exe", 100), session("b", "music.exe", 200)];
+        let list = list(&["game.exe"]);
+
+        for is_whitelist in [BLACKLIST, WHITELIST] {
+            for foreground_pid in [0, 100, 200] {
+                let decisions = plan(&sessions, &config(&list, is_whitelist), false, foreground_pid);
+                assert!(decisions.iter().all(|d| !d.mute));
+            }
+        }
+    }
+
+    #[test]
+    fn no_foreground_pid_treats_every_session_as_background() {
+        let sessions = [session("a", "game.exe", 100), session("b", "music.exe", 200)];
+
+        assert_eq!(mutes(&sessions, &list(&["game.exe", "music.exe"]), BLACKLIST, 0), [true, true]);
+        assert_eq!(mutes(&sessions, &HashSet::new(), WHITELIST, 0), [true, true]);
+    }
+
+    #[test]
+    fn name_matching_ignores_case_on_both_sides() {
+        let sessions = [session("a", "Game.EXE", 100)];
+
+        for entry in ["game.exe", "GAME.EXE", "gAmE.ExE", "Game.EXE"] {
+            assert_eq!(mutes(&sessions, &list(&[entry]), BLACKLIST, 0), [true], "entry {entry:?}");
+        }
+    }
+
+    #[test]
+    fn name_matching_handles_non_ascii_case() {
+        let sessions = [session("a", "ÄUDIO.exe", 100), session("b", "游戏.exe", 200)];
+        let list = list(&["äudio.EXE", "游戏.EXE"]);
+
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 0), [true, true]);
+    }
+
+    #[test]
+    fn name_matching_is_exact_not_substring_or_prefix() {
+        let sessions = [session("a", "game.exe", 100)];
+
+        for entry in ["game", "game.ex", "mygame.exe", "game.exe ", " game.exe"] {
+            assert_eq!(mutes(&sessions, &list(&[entry]), BLACKLIST, 0), [false], "entry {entry:?}");
+        }
+    }
+
+    #[test]
+    fn instance_entry_matches_only_that_pid() {
+        let sessions = [session("a", "game.exe", 100), session("b", "game.exe", 101)];
+        let list = list(&["game.exe [100]"]);
+
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 0), [true, false]);
+        assert_eq!(mutes(&sessions, &list, WHITELIST, 0), [false, true]);
+    }
+
+    #[test]
+    fn instance_entry_bound_to_a_process_ignores_a_reused_pid() {
+        let mut original = session("a", "game.exe", 7);
+        original.process_start_ms = Some(5_000);
+        let (entry, options) = instance_entry(&original);
+        assert_eq!(entry, "game.exe [7]");
+
+        let mut reused = session("b", "game.exe", 7);
+        reused.process_start_ms = Some(9_000);
+        assert!(entry_matches(&entry, &options, &original));
+        assert!(!entry_matches(&entry, &options, &reused));
+        // 没有绑定启动时间的旧条目仍按 PID 匹配
+        assert!(entry_matches(&entry, &RuleOptions::default(), &reused));
+    }
+
+    #[test]
+    fn legacy_instance_entry_is_bound_to_the_first_match() {
+        let mut config = config(&list(&["game.exe [7]", "chat.exe"]), BLACKLIST);
+        let mut original = session("a", "game.exe", 7);
+        original.process_start_ms = Some(5_000);
+        let unbound = unbound_instance_entries(&config, &[original.clone(), original.clone()]);
+        assert_eq!(unbound, [("game.exe [7]".to_string(), 5_000)]);
+        bind_instance_entry(&mut config, "game.exe [7]", 5_000);
+        assert_eq!(config.options("game.exe [7]").process_start_ms, Some(5_000));
+        assert_eq!(config.options("chat.exe"), RuleOptions::default());
+
+        // 已绑定的条目不再改变
+        let mut reused = session("b", "game.exe", 7);
+        reused.process_start_ms = Some(9_000);
+        assert!(unbound_instance_entries(&config, &[reused.clone()]).is_empty());
+        bind_instance_entry(&mut config, "game.exe [7]", 9_000);
+        assert!(is_in_list(&config, &original));
+        assert!(!is_in_list(&config, &reused));
+    }
+
+    #[test]
+    fn instance_entry_is_case_sensitive() {
+        let sessions = [session("a", "Game.exe", 100)];
+
+        assert_eq!(mutes(&sessions, &list(&["Game.exe [100]"]), BLACKLIST, 0), [true]);
+        assert_eq!(mutes(&sessions, &list(&["game.exe [100]"]), BLACKLIST, 0), [false]);
+    }
+
+    #[test]
+    fn instance_entry_requires_exact_format() {
+        let sessions = [session("a", "game.exe", 100)];
+
+        for entry in ["game.exe[100]", "game.exe  [100]", "game.exe [ 100 ]", "game.exe (100)", "game.exe [0100]", "[100]"] {
+            assert_eq!(mutes(&sessions, &list(&[entry]), BLACKLIST, 0), [false], "entry {entry:?}");
+        }
+    }
+
+    #[test]
+    fn instance_entry_for_other_name_does_not_match_same_pid() {
+        let sessions = [session("a", "game.exe", 100)];
+
+        assert_eq!(mutes(&sessions, &list(&["other.exe [100]"]), BLACKLIST, 0), [false]);
+    }
+
+    #[test]
+    fn name_and_instance_entries_combine() {
+        let sessions = [session("a", "game.exe", 100), session("b", "game.exe", 101), session("c", "chat.exe", 300)];
+        let list = list(&["game.exe [101]", "chat.exe"]);
+
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 0), [false, true, true]);
+        assert_eq!(mutes(&sessions, &list, WHITELIST, 0), [true, false, false]);
+    }
+
+    #[test]
+    fn foreground_is_decided_by_pid_not_name() {
+        // 多开时只有真正处于前台的那个实例不静音
+        let sessions = [session("a", "game.exe", 100), session("b", "game.exe", 101)];
+        let list = list(&["game.exe"]);
+
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 100), [false, true]);
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 101), [true, false]);
+    }
+
+    #[test]
+    fn sessions_of_the_same_process_share_a_decision() {
+        let sessions = [session("a", "browser.exe", 100), session("b", "browser.exe", 100)];
+        let list = list(&["browser.exe"]);
+
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 0), [true, true]);
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 100), [false, false]);
+    }
+
+    #[test]
+    fn plan_keeps_session_order_and_ids() {
+        let sessions = [session("x", "b.exe", 2), session("y", "a.exe", 1)];
+        let decisions = plan(&sessions, &config(&list(&["a.exe"]), BLACKLIST), true, 0);
+
+        assert_eq!(decisions, [
+            MuteDecision { id: "x".to_string(), pid: 2, mute: false, action: BackgroundAction::Mute, mute_delay: Duration::ZERO },
+            MuteDecision { id: "y".to_string(), pid: 1, mute: true, action: BackgroundAction::Mute, mute_delay: Duration::ZERO },
+        ]);
+    }
+
+    #[test]
+    fn empty_snapshot_yields_empty_plan() {
+        assert!(plan(&[], &config(&list(&["game.exe"]), WHITELIST), true, 100).is_empty());
+    }
+
+    #[test]
+    fn patterns_cover_every_matching_process() {
+        let sessions = [
+            session("a", "steam.exe", 1),
+            session("b", "steamwebhelper.exe", 2),
+            session("c", "Game-Win64-Shipping.exe", 3),
+            session("d", "music.exe", 4),
+        ];
+        let list = list(&["*steam*", "/game-win64-.*\\.exe/"]);
+
+        assert_eq!(mutes(&sessions, &list, BLACKLIST, 0), [true, true, true, false]);
+        assert_eq!(mutes(&sessions, &list, WHITELIST, 0), [false, false, false, true]);
+    }
+
+    #[test]
+    fn invalid_pattern_matches_nothing() {
+        let sessions = [session("a", "game.exe", 1)];
+        assert_eq!(mutes(&sessions, &list(&["/game(/"]), BLACKLIST, 0), [false]);
+    }
+
+    #[test]
+    fn path_and_publisher_entries_separate_same_named_programs() {
+        let mut game = session("a", "launcher.exe", 1);
+        game.exe_path = Some("/games/shooter/launcher.exe".to_string());
+        game.company_name = Some("Shooter Studio".to_string());
+        let mut tool = session("b", "launcher.exe", 2);
+        tool.exe_path = Some("/tools/launcher.exe".to_string());
+        tool.product_name = Some("Tool Suite".to_string());
+
+        assert_eq!(path_entry(&game).as_deref(), Some("path:/games/shooter/launcher.exe"));
+        assert_eq!(publisher_entry(&game).as_deref(), Some("company:Shooter Studio"));
+        assert_eq!(publisher_entry(&tool).as_deref(), Some("product:Tool Suite"));
+        assert_eq!(publisher_entry(&session("c", "x.exe", 3)), None);
+
+        let sessions = [game.clone(), tool];
+        assert_eq!(mutes(&sessions, &list(&[&path_entry(&game).unwrap()]), BLACKLIST, 0), [true, false]);
+        assert_eq!(mutes(&sessions, &list(&["company:shooter studio"]), BLACKLIST, 0), [true, false]);
+        assert_eq!(mutes(&sessions, &list(&["path:/games/"]), BLACKLIST, 0), [true, false]);
+    }
+
+    #[test]
+    fn is_in_list_covers_both_entry_kinds() {
+        let config = config(&list(&["Music.exe", "game.exe [7]"]), BLACKLIST);
+
+        assert!(is_in_list(&config, &session("a", "music.exe", 1)));
+        assert!(is_in_list(&config, &session("b", "game.exe", 7)));
+        assert!(!is_in_list(&config, &session("c", "game.exe", 8)));
+        assert!(!is_in_list(&config, &session("d", "chat.exe", 7)));
+    }
+
+    fn on_device(mut session: AudioSessionInfo, device_id: &str) -> AudioSessionInfo {
+        session.device_id = device_id.to_string();
+        session
+    }
+
+    #[test]
+    fn device_option_limits_entry_to_that_device() {
+        let sessions = [
+            on_device(session("a", "game.exe", 100), "speakers"),
+            on_device(session("b", "game.exe", 100), "headset"),
+        ];
+        let mut config = config(&list(&["game.exe"]), BLACKLIST);
+        config.set_options("game.exe", RuleOptions { device: Some("headset".to_string()), ..Default::default() });
+
+        let decisions: Vec<bool> = plan(&sessions, &config, true, 0).into_iter().map(|d| d.mute).collect();
+        assert_eq!(decisions, [false, true]);
+
+        config.is_whitelist = WHITELIST;
+        let decisions: Vec<bool> = plan(&sessions, &config, true, 0).into_iter().map(|d| d.mute).collect();
+        assert_eq!(decisions, [true, false]);
+    }
+
+    #[test]
+    fn entries_without_device_cover_every_device() {
+        let sessions = [
+            on_device(session("a", "game.exe", 100), "speakers"),
+            on_device(session("b", "game.exe", 100), "hdmi"),
+        ];
+
+        assert_eq!(mutes(&sessions, &list(&["game.exe"]), BLACKLIST, 0), [true, true]);
+    }
+
+    #[test]
+    fn default_options_are_not_stored() {
+        let mut config = config(&list(&["game.exe"]), BLACKLIST);
+        config.set_options("game.exe", RuleOptions { device: Some("hdmi".to_string()), ..Default::default() });
+        assert_eq!(config.rule_options.len(), 1);
+
+        config.set_options("game.exe", RuleOptions::default());
+        assert!(config.rule_options.is_empty());
+
+        config.set_options("game.exe", RuleOptions { device: Some("hdmi".to_string()), ..Default::default() });
+        config.remove("game.exe");
+        assert!(config.list.is_empty() && config.rule_options.is_empty());
+    }
+
+    #[test]
+    fn adopting_an_unmute_removes_blacklist_entries() {
+        let game = session("a", "game.exe", 100);
+        let mut config = config(&list(&["Game.exe", "game.exe [100]", "music.exe"]), BLACKLIST);
+
+        adopt(&mut config, &game, false);
+        assert_eq!(config.list, list(&["music.exe"]));
+        assert!(!should_mute(&game, &config, true, 0));
+    }
+
+    #[test]
+    fn adopting_an_unmute_extends_the_whitelist() {
+        let game = session("a", "game.exe", 100);
+        let mut config = config(&list(&["music.exe"]), WHITELIST);
+
+        adopt(&mut config, &game, false);
+        assert_eq!(config.list, list(&["music.exe", "game.exe"]));
+        assert!(!should_mute(&game, &config, true, 0));
+    }
+
+    #[test]
+    fn adopting_a_mute_lists_the_process() {
+        let game = session("a", "game.exe", 100);
+        let mut blacklist = config(&list(&[]), BLACKLIST);
+        adopt(&mut blacklist, &game, true);
+        assert!(should_mute(&game, &blacklist, true, 0));
+
+        let mut whitelist = config(&list(&["game.exe"]), WHITELIST);
+        adopt(&mut whitelist, &game, true);
+        assert!(should_mute(&game, &whitelist, true, 0));
+    }
+
+    // steam (1) -> game (2)；browser (10) -> audio service (11)
+    fn processes() -> crate::process::fake::FakeProcesses {
+        crate::process::fake::FakeProcesses::new()
+            .with(1, "steam.exe", 0)
+            .with(2, "game.exe", 1)
+            .with(10, "browser.exe", 0)
+            .with(11, "browser.exe", 10)
+            .with_group(10, 10)
+            .with_group(11, 10)
+    }
+
+    fn relations(descendants: bool, ancestors: bool, same_group: bool) -> ForegroundRelations {
+        ForegroundRelations { descendants, ancestors, same_group }
+    }
+
+    #[test]
+    fn process_tree_is_ignored_without_relations() {
+        let processes = processes();
+        let tree = ProcessTree::new(&processes);
+        let audio_service = session("a", "browser.exe", 11);
+        let config = config(&list(&["browser.exe"]), BLACKLIST);
+
+        assert!(should_mute(&audio_service, &config, true, Foreground { pid: 10, tree: Some(&tree), process: None }));
+    }
+
+    #[test]
+    fn descendants_of_the_foreground_stay_audible() {
+        let processes = processes();
+        let tree = ProcessTree::new(&processes);
+        let audio_service = session("a", "browser.exe", 11);
+        let mut config = config(&list(&[]), WHITELIST);
+        config.foreground_relations = relations(true, false, false);
+
+        assert!(!should_mute(&audio_service, &config, true, Foreground { pid: 10, tree: Some(&tree), process: None }));
+        assert!(should_mute(&audio_service, &config, true, Foreground { pid: 2, tree: Some(&tree), process: None }));
+        // 没有进程树时只按 PID 判断
+        assert!(should_mute(&audio_service, &config, true, 10));
+    }
+
+    #[test]
+    fn relations_on_a_rule_apply_only_to_its_sessions() {
+        let processes = processes();
+        let tree = ProcessTree::new(&processes);
+        let steam = session("a", "steam.exe", 1);
+        let audio_service = session("b", "browser.exe", 11);
+        let mut config = config(&list(&["steam.exe", "browser.exe"]), BLACKLIST);
+        config.set_options("steam.exe", RuleOptions { foreground_relations: relations(false, true, false), ..Default::default() });
+
+        // 游戏在前台时，拉起它的 Steam 视为前台
+        let game = Foreground { pid: 2, tree: Some(&tree), process: None };
+        assert!(is_foreground(&steam, &config, game));
+        assert!(!should_mute(&steam, &config, true, game));
+        // 浏览器的条目没有设置关系
+        assert!(should_mute(&audio_service, &config, true, Foreground { pid: 10, tree: Some(&tree), process: None }));
+        assert_eq!(foreground_relations(&config, &audio_service), ForegroundRelations::default());
+    }
+
+    #[test]
+    fn rule_relations_have_no_effect_in_whitelist_mode() {
+        let processes = processes();
+        let tree = ProcessTree::new(&processes);
+        let steam = session("a", "steam.exe", 1);
+        let audio_service = session("b", "browser.exe", 11);
+        let mut config = config(&list(&["steam.exe"]), WHITELIST);
+        config.set_options("steam.exe", RuleOptions { foreground_relations: relations(true, true, true), ..Default::default() });
+
+        // 白名单中的 Steam 本来就不会被静音，条目上的关系也不会波及未列出的会话
+        assert!(!should_mute(&steam, &config, true, Foreground { pid: 10, tree: Some(&tree), process: None }));
+        assert_eq!(foreground_relations(&config, &audio_service), ForegroundRelations::default());
+        assert!(should_mute(&audio_service, &config, true, Foreground { pid: 10, tree: Some(&tree), process: None }));
+        // 全局前台范围照常生效
+        config.foreground_relations = relations(true, false, false);
+        assert!(!should_mute(&audio_service, &config, true, Foreground { pid: 10, tree: Some(&tree), process: None }));
+    }
+
+    #[test]
+    fn same_process_group_counts_as_foreground() {
+        let processes = processes();
+        let tree = ProcessTree::new(&processes);
+        let browser = session("a", "browser.exe", 10);
+        let mut config = config(&list(&["browser.exe"]), BLACKLIST);
+        config.foreground_relations = relations(false, false, true);
+
+        assert!(!should_mute(&browser, &config, true, Foreground { pid: 11, tree: Some(&tree), process: None }));
+        assert!(should_mute(&browser, &config, true, Foreground { pid: 2, tree: Some(&tree), process: None }));
+    }
+
+    fn group(name: &str, members: &[&str], mode: GroupMode) -> AppGroup {
+        let mut group = AppGroup::new(name);
+        for member in members {
+            group.add_member(member).unwrap();
+        }
+        group.mode = mode;
+        group
+    }
+
+    #[test]
+    fn any_focused_member_keeps_the_whole_group_audible() {
+        let sessions = [
+            session("a", "game.exe", 100),
+            session("b", "discord.exe", 200),
+            session("c", "music.exe", 300),
+            session("d", "browser.exe", 400),
+        ];
+        let mut config = config(&list(&["game.exe", "discord.exe", "music.exe", "browser.exe"]), BLACKLIST);
+        config.groups.push(group("游戏", &["game.exe", "discord.exe", "music.exe"], GroupMode::AnyFocused));
+
+        let mutes = |foreground: u32| plan(&sessions, &config, true, foreground).into_iter().map(|d| d.mute).collect::<Vec<_>>();
+        assert_eq!(mutes(100), [false, false, false, true]);
+        assert_eq!(mutes(200), [false, false, false, true]);
+        assert_eq!(mutes(400), [true, true, true, false]);
+        // 停止运行时分组不起作用
+        assert!(plan(&sessions, &config, false, 400).iter().all(|d| !d.mute));
+    }
+
+    #[test]
+    fn focused_member_without_a_session_counts() {
+        let sessions = [session("a", "music.exe", 300)];
+        let mut config = config(&list(&["music.exe"]), BLACKLIST);
+        config.groups.push(group("游戏", &["discord.exe", "music.exe"], GroupMode::AnyFocused));
+
+        let discord = session("", "discord.exe", 200);
+        let focused = Foreground { pid: 200, tree: None, process: Some(&discord) };
+        assert!(!plan(&sessions, &config, true, focused)[0].mute);
+        assert!(plan(&sessions, &config, true, 200)[0].mute);
+    }
+
+    #[test]
+    fn followers_are_audible_whenever_the_leader_is() {
+        let game = session("a", "game.exe", 100);
+        let discord = session("b", "discord.exe", 200);
+        let browser = session("c", "browser.exe", 400);
+        let follow = group("语音", &["game.exe", "discord.exe"], GroupMode::FollowMember("game.exe".to_string()));
+
+        // 游戏不在黑名单中，始终有声，Discord 跟随它
+        let mut unlisted = config(&list(&["discord.exe"]), BLACKLIST);
+        unlisted.groups.push(follow.clone());
+        let sessions = [game.clone(), discord.clone(), browser.clone()];
+        assert_eq!(plan(&sessions, &unlisted, true, 400).into_iter().map(|d| d.mute).collect::<Vec<_>>(), [false, false, false]);
+        // 游戏没有运行时 Discord 按列表静音
+        assert!(plan(&[discord, browser], &unlisted, true, 400)[0].mute);
+
+        // 跟随是单向的：Discord 在前台不会让游戏有声
+        let mut listed = config(&list(&["game.exe", "discord.exe"]), BLACKLIST);
+        listed.groups.push(follow);
+        let mutes = |foreground: u32| plan(&sessions, &listed, true, foreground).into_iter().map(|d| d.mute).collect::<Vec<_>>();
+        assert_eq!(mutes(100), [false, false, false]);
+        assert_eq!(mutes(200), [true, false, false]);
+        assert_eq!(mutes(400), [true, true, false]);
+    }
+
+    #[test]
+    fn group_members_are_validated_and_leader_removal_resets_the_mode() {
+        let mut group = group("语音", &["game.exe", "discord.exe"], GroupMode::FollowMember("game.exe".to_string()));
+        assert!(group.add_member("/(/").is_err());
+        group.add_member("discord.exe").unwrap();
+        assert_eq!(group.members, ["game.exe", "discord.exe"]);
+
+        group.remove_member("game.exe");
+        assert_eq!(group.members, ["discord.exe"]);
+        assert_eq!(group.mode, GroupMode::AnyFocused);
+    }
+
+    #[test]
+    fn quietest_matching_action_wins() {
+        let game = session("a", "game.exe", 100);
+        let mut config = config(&list(&["game.exe", "*game*", "music.exe"]), BLACKLIST);
+        assert_eq!(action(&config, &game), BackgroundAction::Mute);
+
+        config.background_action = BackgroundAction::Duck(50);
+        config.set_options("game.exe", RuleOptions { action: Some(BackgroundAction::Cap(30)), ..Default::default() });
+        config.set_options("*game*", RuleOptions { action: Some(BackgroundAction::Duck(40)), ..Default::default() });
+        config.set_options("music.exe", RuleOptions { action: Some(BackgroundAction::Mute), ..Default::default() });
+        assert_eq!(action(&config, &game), BackgroundAction::Cap(30));
+        assert_eq!(action(&config, &session("b", "other.exe", 200)), BackgroundAction::Duck(50));
+
+        assert_eq!(BackgroundAction::Duck(20).volume(0.5), Some(0.1));
+        assert_eq!(BackgroundAction::Cap(30).volume(0.2), Some(0.2));
+        assert_eq!(BackgroundAction::Mute.volume(0.5), None);
+    }
+
+    #[test]
+    fn longest_matching_mute_delay_wins() {
+        let game = session("a", "game.exe", 100);
+        let mut config = config(&list(&["game.exe", "*game*", "music.exe"]), BLACKLIST);
+        assert_eq!(mute_delay(&config, &game), Duration::ZERO);
+
+        config.set_options("game.exe", RuleOptions { mute_delay_secs: 3, ..Default::default() });
+        config.set_options("*game*", RuleOptions { mute_delay_secs: 5, ..Default::default() });
+        config.set_options("music.exe", RuleOptions { mute_delay_secs: 10, ..Default::default() });
+        assert_eq!(mute_delay(&config, &game), Duration::from_secs(5));
+        assert_eq!(mute_delay(&config, &session("b", "other.exe", 200)), Duration::ZERO);
+    }
+}
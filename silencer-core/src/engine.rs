@@ -0,0 +1,298 @@
+use std::time::Instant;
+
+use crate::clock::{Clock, SystemClock};
+
+use crate::audio::{self, AudioManager, AudioSessionInfo, ChangeNotifier, ExternalChange, SessionNotifier};
+use crate::config::{BackgroundAction, Config, ExternalChangePolicy};
+use crate::decision;
+use crate::foreground::{self, FocusNotifier, ForegroundProvider};
+use crate::journal::{Journal, Repair};
+use crate::process;
+use crate::scheduler::{Scheduler, Tick};
+
+/// 引擎自己对配置的修改。前端以整份配置替换引擎的配置（见 [`Engine::set_config`]），
+/// 因此引擎的修改以增量发给前端，由前端合并到自己的配置中再发回
+#[derive(Clone, Debug)]
+pub enum ConfigChange {
+    /// 把用户对会话的手动修改记为规则，见 [`decision::adopt`]
+    Adopt { session: Box<AudioSessionInfo>, muted: bool },
+    /// 把旧版本保存的实例条目绑定到第一次命中的进程，见 [`decision::bind_instance_entry`]
+    BindInstance { entry: String, process_start_ms: u64 },
+}
+
+impl ConfigChange {
+    pub fn apply(&self, config: &mut Config) {
+        match self {
+            ConfigChange::Adopt { session, muted } => decision::adopt(config, session, *muted),
+            ConfigChange::BindInstance { entry, process_start_ms } => decision::bind_instance_entry(config, entry, *process_start_ms),
+        }
+    }
+}
+
+/// 静音引擎：持有配置、音频后端、前台窗口提供者与调度器。
+/// 前端（图形界面、命令行或守护进程）需在前台切换时调用 [`Engine::focus_changed`]、会话变化时调用 [`Engine::sessions_changed`]，
+/// 在 [`Engine::next_deadline`] 到达时调用 [`Engine::tick`]，并在修改配置后调用 [`Engine::enforce`]。
+/// 图形界面通常不直接持有引擎，而是通过 [`crate::worker::Worker`] 在后台线程上运行它。
+pub struct Engine {
+    config: Config,
+    running: bool,
+    audio: AudioManager,
+    foreground: Box<dyn ForegroundProvider>,
+    focus_pending: bool,
+    sessions_pending: bool,
+    clock: Box<dyn Clock>,
+    scheduler: Scheduler,
+    sessions: Vec<AudioSessionInfo>,
+    // 同步时发现会话列表或窗口标题有变化，下一次 tick 需要报告会话已刷新
+    sessions_updated: bool,
+    repairs: Vec<Repair>,
+    // 引擎自己修改配置（把手动修改记为规则、绑定实例条目）的次数，即最近一次修改的版本号
+    config_revision: u64,
+    // 前端尚未确认合并的修改及其版本号
+    config_changes: Vec<(u64, ConfigChange)>,
+}
+
+impl Engine {
+    /// 使用当前平台的音频后端与前台窗口提供者创建引擎，前台切换时调用 `notify`。
+    /// Windows 下会在调用线程上初始化 COM，此后引擎只能在该线程上使用
+    pub fn new(config: Config, notify: FocusNotifier) -> audio::Result<Self> {
+        let mut audio = AudioManager::new()?.with_process_info(process::platform_provider());
+        if let Some(path) = Journal::default_path() {
+            audio = audio.with_journal(Journal::new(path));
+        }
+        let foreground = foreground::platform_provider(notify);
+        Ok(Self::with_parts(config, audio, foreground))
+    }
+
+    /// 使用给定的组件创建引擎。若音频管理器启用了日志，先修复上次异常退出时遗留的会话
+    pub fn with_parts(config: Config, audio: AudioManager, foreground: Box<dyn ForegroundProvider>) -> Self {
+        let repairs = audio.repair_from_journal().unwrap_or_default();
+        let sessions = audio.get_active_sessions().unwrap_or_default();
+        Self {
+            config,
+            running: false,
+            audio,
+            foreground,
+            focus_pending: false,
+            sessions_pending: false,
+            clock: Box::new(SystemClock),
+            scheduler: Scheduler::new(SystemClock.now()),
+            sessions,
+            sessions_updated: false,
+            repairs,
+            config_revision: 0,
+            config_changes: Vec::new(),
+        }
+    }
+
+    /// 替换时间来源（例如在测试中使用 [`crate::clock::VirtualClock`]），调度器从新时钟的当前时间重新开始计时
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.scheduler = Scheduler::new(clock.now());
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// 引擎时钟的当前时间
+    pub fn now(&self) -> Instant {
+        self.clock.now()
+    }
+
+    /// 启动时根据日志修复的会话
+    pub fn repairs(&self) -> &[Repair] {
+        &self.repairs
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// 引擎自己修改配置的次数，即最近一次修改的版本号
+    pub fn config_revision(&self) -> u64 {
+        self.config_revision
+    }
+
+    /// 前端尚未确认合并的修改及其版本号，按版本号递增。前端应把版本号大于自己已合并版本的修改应用到自己的配置上
+    pub fn config_changes(&self) -> &[(u64, ConfigChange)] {
+        &self.config_changes
+    }
+
+    /// 替换配置并立即生效。`revision` 为 `config` 已合并的引擎修改的版本号；
+    /// 之后的修改是前端生成这份配置时还没有看到的，重新应用到新配置上，不会被覆盖
+    pub fn set_config(&mut self, mut config: Config, revision: u64) {
+        self.config_changes.retain(|(changed, _)| *changed > revision);
+        for (_, change) in &self.config_changes {
+            change.apply(&mut config);
+        }
+        self.config = config;
+        self.enforce();
+    }
+
+    fn change_config(&mut self, change: ConfigChange) {
+        change.apply(&mut self.config);
+        self.config_revision += 1;
+        self.config_changes.push((self.config_revision, change));
+    }
+
+    /// 修改配置后需调用 [`Engine::enforce`] 使其立即生效
+    pub fn config_mut(&mut self) -> &mut Config {
+        &mut self.config
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// 停止运行时把我们修改过的会话恢复到原始状态
+    pub fn set_running(&mut self, running: bool) {
+        self.running = running;
+        if running {
+            self.enforce();
+        } else {
+            let _ = self.audio.restore_saved_states();
+            self.scheduler.set_fading(false, self.clock.now());
+            self.scheduler.set_grace_deadline(None);
+        }
+    }
+
+    /// 最近一次枚举到的音频会话
+    pub fn sessions(&self) -> &[AudioSessionInfo] {
+        &self.sessions
+    }
+
+    pub fn refresh_sessions(&mut self) {
+        if let Ok(sessions) = self.audio.get_active_sessions() {
+            self.sessions = sessions;
+        }
+        self.scheduler.mark_refreshed(self.clock.now());
+    }
+
+    /// 立即按当前配置与前台窗口同步所有会话的静音状态。同步时枚举到的会话与窗口标题有变化时一并更新会话列表
+    pub fn enforce(&mut self) {
+        let foreground_pid = self.foreground.foreground_pid();
+        let now = self.clock.now();
+        let result = self.audio.update_mute_status_at(&self.config, self.running, foreground_pid, now);
+        self.scheduler.set_fading(self.audio.is_fading(), now);
+        self.scheduler.set_grace_deadline(self.audio.next_grace_deadline());
+        if let Ok(sessions) = result {
+            for (entry, process_start_ms) in decision::unbound_instance_entries(&self.config, &sessions) {
+                self.change_config(ConfigChange::BindInstance { entry, process_start_ms });
+            }
+            let changed = sessions.len() != self.sessions.len()
+                || sessions.iter().zip(&self.sessions).any(|(new, old)| new.id != old.id || new.window_title != old.window_title);
+            if changed {
+                self.sessions = sessions;
+                self.sessions_updated = true;
+            }
+        }
+    }
+
+    /// 记录一次前台切换，在防抖间隔后由 [`Engine::tick`] 处理。多次切换会合并为一次
+    pub fn focus_changed(&mut self) {
+        self.focus_pending = true;
+    }
+
+    /// 让音频后端在会话出现、状态变化或断开时调用 `notify`，调用方应随后调用 [`Engine::sessions_changed`]。
+    /// 后端支持事件时不再定时轮询会话列表
+    pub fn watch_sessions(&mut self, notify: SessionNotifier) {
+        if let Ok(true) = self.audio.watch_sessions(notify) {
+            self.scheduler.set_poll_sessions(false);
+        }
+    }
+
+    /// 让音频后端在用户从系统音量合成器修改会话时调用 `notify`，调用方应随后调用 [`Engine::external_change`]
+    pub fn watch_external_changes(&mut self, notify: ChangeNotifier) {
+        let _ = self.audio.watch_external_changes(notify);
+    }
+
+    /// 处理用户对会话的手动修改：只关心与规则冲突的后台会话，按配置的策略重新应用、暂停或记为规则
+    pub fn external_change(&mut self, change: ExternalChange) {
+        if !self.running {
+            return;
+        }
+        // 会话的实际状态已不是我们上次设置的，下一次同步需要重新检查
+        self.audio.forget_applied(&change.session_id);
+        let Some(session) = self.sessions.iter().find(|session| session.id == change.session_id).cloned() else {
+            // 缓存的会话列表已过时，立即重新枚举并同步
+            self.sessions_pending = true;
+            return;
+        };
+        // 宽限期内的会话本应有声，修改它不与规则冲突；渐变中的会话正由我们修改，收到的多半是渐变本身的通知
+        if self.audio.in_grace_period(&session) || self.audio.is_session_fading(&session) {
+            return;
+        }
+        let foreground_pid = self.foreground.foreground_pid();
+        // 降低音量的动作不改变静音状态，只有静音状态与计划不符才算冲突
+        let conflicts = self.audio.with_foreground(&self.config, foreground_pid, &self.sessions, |foreground| {
+            let planned_muted = decision::plan(&self.sessions, &self.config, true, foreground)
+                .into_iter()
+                .find(|decision| decision.id == session.id)
+                .is_some_and(|decision| decision.mute && decision.action == BackgroundAction::Mute);
+            !decision::is_foreground(&session, &self.config, foreground) && planned_muted != change.muted
+        });
+        if !conflicts {
+            return;
+        }
+        match self.config.external_change_policy {
+            // 下一次周期同步会重新应用规则
+            ExternalChangePolicy::Override => {}
+            ExternalChangePolicy::PauseUntilFocusChange => self.audio.pause(&session, foreground_pid),
+            ExternalChangePolicy::AdoptAsRule => {
+                self.change_config(ConfigChange::Adopt { session: Box::new(session), muted: change.muted });
+                self.enforce();
+            }
+        }
+    }
+
+    /// 记录一次会话变化，下一次 [`Engine::tick`] 会立即刷新会话列表并同步静音状态，不经过防抖
+    pub fn sessions_changed(&mut self) {
+        self.sessions_pending = true;
+    }
+
+    /// 下一次需要调用 [`Engine::tick`] 的时间点，`None` 表示只需等待事件
+    pub fn next_deadline(&self) -> Option<Instant> {
+        if self.sessions_pending {
+            return Some(self.clock.now());
+        }
+        self.scheduler.next_deadline(self.focus_pending, self.running)
+    }
+
+    /// 按防抖/周期同步规则刷新会话与同步静音状态，返回本次执行的动作
+    pub fn tick(&mut self) -> Tick {
+        let now = self.clock.now();
+        let mut tick = self.scheduler.tick(now, self.focus_pending, self.running);
+        let scheduled = tick.enforce;
+        if self.sessions_pending {
+            // 新出现的会话需要立即分类并静音，否则在下一次同步前会漏音
+            self.sessions_pending = false;
+            tick.refresh_sessions = true;
+            if self.running && !scheduled {
+                // 这次同步不经过防抖，但同样推迟下一次周期同步
+                self.scheduler.mark_enforced(now);
+                tick.enforce = true;
+            }
+        }
+        if tick.refresh_sessions {
+            self.refresh_sessions();
+        }
+        if tick.enforce {
+            // 仅处理最新一次切换状态；会话变化触发的同步不消耗防抖中的切换，防抖结束后照常处理
+            if scheduled {
+                self.focus_pending = false;
+            }
+            self.enforce();
+        }
+        if tick.step_fades {
+            let _ = self.audio.step_fades(now);
+            self.scheduler.set_fading(self.audio.is_fading(), now);
+        }
+        tick.refresh_sessions |= std::mem::take(&mut self.sessions_updated);
+        tick
+    }
+}
+
+impl Drop for Engine {
+    fn drop(&mut self) {
+        // 退出时尝试将我们修改过的会话恢复到原始静音状态
+        let _ = self.audio.restore_saved_states();
+    }
+}
@@ -0,0 +1,77 @@
+use std::time::{Duration, Instant};
+
+use crate::config::{Fade, FadeCurve};
+
+/// 一段音量渐变：从 `from` 开始，经过 `duration` 按曲线变到 `to`。本身不读取时钟，由调用方传入当前时间
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ramp {
+    pub from: f32,
+    pub to: f32,
+    start: Instant,
+    duration: Duration,
+    curve: FadeCurve,
+}
+
+impl Ramp {
+    pub fn new(from: f32, to: f32, start: Instant, fade: Fade) -> Self {
+        Self { from, to, start, duration: Duration::from_millis(fade.duration_ms as u64), curve: fade.curve }
+    }
+
+    fn progress(&self, now: Instant) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        now.saturating_duration_since(self.start).as_secs_f32() / self.duration.as_secs_f32()
+    }
+
+    /// `now` 时应设置的音量
+    pub fn volume_at(&self, now: Instant) -> f32 {
+        self.from + (self.to - self.from) * self.curve.apply(self.progress(now))
+    }
+
+    pub fn is_finished(&self, now: Instant) -> bool {
+        self.progress(now) >= 1.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fade(duration_ms: u32, curve: FadeCurve) -> Fade {
+        Fade { duration_ms, curve }
+    }
+
+    #[test]
+    fn linear_ramp_interpolates_and_finishes() {
+        let t0 = Instant::now();
+        let ramp = Ramp::new(1.0, 0.2, t0, fade(100, FadeCurve::Linear));
+
+        assert_eq!(ramp.volume_at(t0), 1.0);
+        assert!((ramp.volume_at(t0 + Duration::from_millis(50)) - 0.6).abs() < 1e-6);
+        assert!(!ramp.is_finished(t0 + Duration::from_millis(99)));
+        assert!(ramp.is_finished(t0 + Duration::from_millis(100)));
+        // 超过时长后停在终点
+        assert!((ramp.volume_at(t0 + Duration::from_secs(1)) - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn curves_share_endpoints() {
+        for curve in [FadeCurve::Linear, FadeCurve::EaseIn, FadeCurve::EaseOut, FadeCurve::EaseInOut] {
+            assert_eq!(curve.apply(0.0), 0.0);
+            assert_eq!(curve.apply(1.0), 1.0);
+        }
+        assert!(FadeCurve::EaseIn.apply(0.5) < 0.5);
+        assert!(FadeCurve::EaseOut.apply(0.5) > 0.5);
+        assert_eq!(FadeCurve::EaseInOut.apply(0.5), 0.5);
+    }
+
+    #[test]
+    fn zero_duration_jumps_to_the_end() {
+        let t0 = Instant::now();
+        let ramp = Ramp::new(0.0, 0.8, t0, fade(0, FadeCurve::EaseIn));
+
+        assert!(ramp.is_finished(t0));
+        assert_eq!(ramp.volume_at(t0), 0.8);
+    }
+}
@@ -0,0 +1,61 @@
+use std::sync::Arc;
+
+#[cfg(target_os = "linux")]
+pub mod hyprland;
+#[cfg(target_os = "linux")]
+pub mod sway;
+#[cfg(windows)]
+pub mod win_event;
+#[cfg(target_os = "linux")]
+pub mod x11;
+
+/// 前台窗口切换时调用的通知函数（可能在任意线程上被调用）
+pub type FocusNotifier = Arc<dyn Fn() + Send + Sync>;
+
+/// 前台窗口提供者：报告当前前台进程，并在前台切换时调用构造时传入的 [`FocusNotifier`]
+pub trait ForegroundProvider: Send {
+    /// 当前前台窗口所属进程的 PID，未知时返回 0
+    fn foreground_pid(&self) -> u32;
+}
+
+/// 无法获取前台窗口时使用：始终视为没有前台进程
+pub struct NullProvider;
+
+impl ForegroundProvider for NullProvider {
+    fn foreground_pid(&self) -> u32 {
+        0
+    }
+}
+
+/// 返回当前平台可用的前台窗口提供者，全部不可用时退回到 [`NullProvider`]
+pub fn platform_provider(notify: FocusNotifier) -> Box<dyn ForegroundProvider> {
+    #[cfg(windows)]
+    {
+        Box::new(win_event::WinEventProvider::new(notify))
+    }
+    #[cfg(target_os = "linux")]
+    {
+        // Wayland 下没有全局的活动窗口 API，优先使用合成器 IPC；DISPLAY 可能来自 XWayland，因此放在最后
+        if std::env::var_os("SWAYSOCK").is_some()
+            && let Ok(provider) = sway::SwayProvider::from_env(Arc::clone(&notify))
+        {
+            return Box::new(provider);
+        }
+        if std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some()
+            && let Ok(provider) = hyprland::HyprlandProvider::from_env(Arc::clone(&notify))
+        {
+            return Box::new(provider);
+        }
+        if std::env::var_os("DISPLAY").is_some()
+            && let Ok(provider) = x11::X11Provider::connect(notify)
+        {
+            return Box::new(provider);
+        }
+        Box::new(NullProvider)
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        let _ = notify;
+        Box::new(NullProvider)
+    }
+}
@@ -0,0 +1,186 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread::JoinHandle;
+
+use serde_json::Value;
+
+use super::{FocusNotifier, ForegroundProvider};
+
+/// 通过命令套接字（`.socket.sock`）查询当前活动窗口的 PID，没有活动窗口时返回 0
+fn query_active_pid(dir: &Path) -> io::Result<u32> {
+    let mut stream = UnixStream::connect(dir.join(".socket.sock"))?;
+    stream.write_all(b"j/activewindow")?;
+    let mut reply = String::new();
+    stream.read_to_string(&mut reply)?;
+    let window: Value = serde_json::from_str(&reply).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok(window["pid"].as_u64().unwrap_or(0) as u32)
+}
+
+/// Hyprland 的前台窗口提供者：监听事件套接字（`.socket2.sock`）中的 `activewindowv2` 事件，
+/// 再通过命令套接字解析出活动窗口的 PID
+pub struct HyprlandProvider {
+    pid: Arc<AtomicU32>,
+    events: UnixStream,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HyprlandProvider {
+    /// 根据 `$HYPRLAND_INSTANCE_SIGNATURE` 定位套接字目录（新版位于 `$XDG_RUNTIME_DIR/hypr`，旧版位于 `/tmp/hypr`）
+    pub fn from_env(notify: FocusNotifier) -> io::Result<Self> {
+        let signature = std::env::var_os("HYPRLAND_INSTANCE_SIGNATURE")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "未设置 HYPRLAND_INSTANCE_SIGNATURE"))?;
+        let mut candidates = Vec::new();
+        if let Some(runtime) = std::env::var_os("XDG_RUNTIME_DIR") {
+            candidates.push(PathBuf::from(runtime).join("hypr").join(&signature));
+        }
+        candidates.push(PathBuf::from("/tmp/hypr").join(&signature));
+
+        let dir = candidates
+            .into_iter()
+            .find(|dir| dir.join(".socket2.sock").exists())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "找不到 Hyprland 事件套接字"))?;
+        Self::connect(dir, notify)
+    }
+
+    pub fn connect(dir: PathBuf, notify: FocusNotifier) -> io::Result<Self> {
+        let pid = Arc::new(AtomicU32::new(query_active_pid(&dir)?));
+        let events = UnixStream::connect(dir.join(".socket2.sock"))?;
+
+        let thread = {
+            let reader = BufReader::new(events.try_clone()?);
+            let pid = Arc::clone(&pid);
+            std::thread::spawn(move || {
+                for line in reader.lines() {
+                    let Ok(line) = line else { break };
+                    let Some(address) = line.strip_prefix("activewindowv2>>") else { continue };
+                    // 地址为空表示当前没有活动窗口
+                    let focused = if address.trim().is_empty() || address == "," {
+                        0
+                    } else {
+                        query_active_pid(&dir).unwrap_or(0)
+                    };
+                    pid.store(focused, Ordering::SeqCst);
+                    notify();
+                }
+            })
+        };
+
+        Ok(Self { pid, events, thread: Some(thread) })
+    }
+}
+
+impl ForegroundProvider for HyprlandProvider {
+    fn foreground_pid(&self) -> u32 {
+        self.pid.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for HyprlandProvider {
+    fn drop(&mut self) {
+        // 关闭套接字让监听线程的阻塞读取返回
+        let _ = self.events.shutdown(Shutdown::Both);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::time::Duration;
+
+    /// 模拟 Hyprland：命令套接字依次返回给定的活动窗口 JSON（最后一个重复使用），事件套接字按脚本推送事件行。
+    /// 每批事件在返回的发送端收到一次信号后才推送，避免测试读取状态前后续事件已被处理
+    fn fake_hyprland(name: &str, windows: Vec<&'static str>, events: Vec<Vec<&'static str>>) -> (PathBuf, Sender<()>) {
+        let dir = std::env::temp_dir().join(format!("silencer-hypr-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let commands = UnixListener::bind(dir.join(".socket.sock")).unwrap();
+        std::thread::spawn(move || {
+            let mut windows = windows.into_iter();
+            let mut last = "{}";
+            for stream in commands.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut request = [0u8; 64];
+                let len = stream.read(&mut request).unwrap();
+                assert_eq!(&request[..len], b"j/activewindow");
+                if let Some(window) = windows.next() {
+                    last = window;
+                }
+                stream.write_all(last.as_bytes()).unwrap();
+            }
+        });
+
+        let listener = UnixListener::bind(dir.join(".socket2.sock")).unwrap();
+        let (step, steps) = channel();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            for batch in events {
+                if steps.recv().is_err() {
+                    return;
+                }
+                for event in batch {
+                    stream.write_all(format!("{}\n", event).as_bytes()).unwrap();
+                }
+            }
+            // 保持连接打开，直到提供者关闭
+            let _ = stream.read(&mut [0u8; 1]);
+        });
+
+        (dir, step)
+    }
+
+    fn notifier() -> (FocusNotifier, Receiver<()>) {
+        let (tx, rx) = channel();
+        (Arc::new(move || { let _ = tx.send(()); }), rx)
+    }
+
+    #[test]
+    fn follows_activewindowv2_events() {
+        let windows = vec![
+            r#"{"address": "0x1", "pid": 100, "class": "firefox"}"#,
+            r#"{"address": "0x2", "pid": 200, "class": "steam"}"#,
+        ];
+        let events = vec![
+            vec!["workspace>>2", "activewindow>>steam,Steam", "activewindowv2>>2"],
+            vec!["activewindow>>,", "activewindowv2>>"],
+        ];
+        let (dir, step) = fake_hyprland("events", windows, events);
+        let (notify, rx) = notifier();
+
+        let provider = HyprlandProvider::connect(dir.clone(), notify).unwrap();
+        assert_eq!(provider.foreground_pid(), 100);
+
+        step.send(()).unwrap();
+        rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(provider.foreground_pid(), 200);
+        step.send(()).unwrap();
+        rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(provider.foreground_pid(), 0);
+        // 其它事件不会触发通知
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        drop(provider);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn no_active_window_at_startup() {
+        let (dir, _step) = fake_hyprland("empty", vec!["{}"], vec![]);
+        let (notify, _rx) = notifier();
+
+        let provider = HyprlandProvider::connect(dir.clone(), notify).unwrap();
+        assert_eq!(provider.foreground_pid(), 0);
+
+        drop(provider);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
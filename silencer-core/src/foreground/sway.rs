@@ -0,0 +1,262 @@
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::thread::JoinHandle;
+
+use serde_json::Value;
+
+use super::{FocusNotifier, ForegroundProvider};
+
+const MAGIC: &[u8; 6] = b"i3-ipc";
+const GET_TREE: u32 = 4;
+const SUBSCRIBE: u32 = 2;
+// 事件消息的类型最高位为 1
+const EVENT_WORKSPACE: u32 = 0x8000_0000;
+const EVENT_WINDOW: u32 = 0x8000_0003;
+
+fn send(stream: &mut UnixStream, kind: u32, payload: &[u8]) -> io::Result<()> {
+    let mut message = Vec::with_capacity(14 + payload.len());
+    message.extend_from_slice(MAGIC);
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&kind.to_ne_bytes());
+    message.extend_from_slice(payload);
+    stream.write_all(&message)
+}
+
+fn receive(stream: &mut UnixStream) -> io::Result<(u32, Value)> {
+    let mut header = [0u8; 14];
+    stream.read_exact(&mut header)?;
+    if &header[..6] != MAGIC {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "不是 i3-ipc 消息"));
+    }
+    let len = u32::from_ne_bytes(header[6..10].try_into().unwrap()) as usize;
+    let kind = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+    let mut payload = vec![0u8; len];
+    stream.read_exact(&mut payload)?;
+    let value = serde_json::from_slice(&payload).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    Ok((kind, value))
+}
+
+/// 在布局树中查找获得焦点的窗口，返回其 PID（聚焦的是空工作区时返回 0）
+fn focused_pid(node: &Value) -> u32 {
+    if node["focused"].as_bool() == Some(true) {
+        return node["pid"].as_u64().unwrap_or(0) as u32;
+    }
+    ["nodes", "floating_nodes"]
+        .iter()
+        .filter_map(|key| node[*key].as_array())
+        .flatten()
+        .map(focused_pid)
+        .find(|&pid| pid != 0)
+        .unwrap_or(0)
+}
+
+fn query_focused_pid(socket: &Path) -> io::Result<u32> {
+    let mut stream = UnixStream::connect(socket)?;
+    send(&mut stream, GET_TREE, b"")?;
+    let (_, tree) = receive(&mut stream)?;
+    Ok(focused_pid(&tree))
+}
+
+/// sway（i3 兼容 IPC）的前台窗口提供者：订阅 window/workspace 事件，焦点变化时解析出窗口 PID
+pub struct SwayProvider {
+    pid: Arc<AtomicU32>,
+    events: UnixStream,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl SwayProvider {
+    /// 连接 `$SWAYSOCK` 指定的 IPC 套接字
+    pub fn from_env(notify: FocusNotifier) -> io::Result<Self> {
+        let socket = std::env::var_os("SWAYSOCK")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "未设置 SWAYSOCK"))?;
+        Self::connect(PathBuf::from(socket), notify)
+    }
+
+    pub fn connect(socket: PathBuf, notify: FocusNotifier) -> io::Result<Self> {
+        let pid = Arc::new(AtomicU32::new(query_focused_pid(&socket)?));
+
+        let mut events = UnixStream::connect(&socket)?;
+        send(&mut events, SUBSCRIBE, br#"["window","workspace"]"#)?;
+        let (_, reply) = receive(&mut events)?;
+        if reply["success"].as_bool() != Some(true) {
+            return Err(io::Error::other("sway 拒绝了事件订阅"));
+        }
+
+        let thread = {
+            let mut events = events.try_clone()?;
+            let pid = Arc::clone(&pid);
+            std::thread::spawn(move || {
+                while let Ok((kind, event)) = receive(&mut events) {
+                    if event["change"] != "focus" {
+                        continue;
+                    }
+                    let focused = match kind {
+                        EVENT_WINDOW => event["container"]["pid"].as_u64().unwrap_or(0) as u32,
+                        // 切换到工作区时可能没有窗口获得焦点，需要重新读取布局树
+                        EVENT_WORKSPACE => query_focused_pid(&socket).unwrap_or(0),
+                        _ => continue,
+                    };
+                    pid.store(focused, Ordering::SeqCst);
+                    notify();
+                }
+            })
+        };
+
+        Ok(Self { pid, events, thread: Some(thread) })
+    }
+}
+
+impl ForegroundProvider for SwayProvider {
+    fn foreground_pid(&self) -> u32 {
+        self.pid.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for SwayProvider {
+    fn drop(&mut self) {
+        // 关闭套接字让监听线程的阻塞读取返回
+        let _ = self.events.shutdown(Shutdown::Both);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::os::unix::net::UnixListener;
+    use std::sync::mpsc::{channel, Receiver, Sender};
+    use std::time::Duration;
+
+    fn tree(focused_pid: Option<u32>) -> Value {
+        json!({
+            "type": "root",
+            "focused": false,
+            "nodes": [{
+                "type": "workspace",
+                "focused": focused_pid.is_none(),
+                "nodes": [
+                    {"type": "con", "pid": 100, "focused": focused_pid == Some(100), "nodes": []},
+                    {"type": "con", "pid": 200, "focused": focused_pid == Some(200), "nodes": []}
+                ],
+                "floating_nodes": [
+                    {"type": "floating_con", "pid": 300, "focused": focused_pid == Some(300), "nodes": []}
+                ]
+            }]
+        })
+    }
+
+    /// 模拟 sway：命令连接依次返回给定的布局树（最后一个重复使用），订阅连接按脚本推送事件。
+    /// 每批事件在返回的发送端收到一次信号后才推送，避免测试读取初始状态前事件已被处理
+    fn fake_sway(name: &str, trees: Vec<Value>, script: Vec<Vec<(u32, Value)>>) -> (PathBuf, Sender<()>) {
+        let socket = std::env::temp_dir().join(format!("silencer-sway-{}-{}.sock", std::process::id(), name));
+        let _ = std::fs::remove_file(&socket);
+        let listener = UnixListener::bind(&socket).unwrap();
+        let (step, steps) = channel::<()>();
+
+        std::thread::spawn(move || {
+            let mut steps = Some(steps);
+            let mut trees = trees.into_iter();
+            let mut last_tree = Value::Null;
+            let mut script = Some(script);
+            for stream in listener.incoming() {
+                let Ok(mut stream) = stream else { break };
+                let mut header = [0u8; 14];
+                if stream.read_exact(&mut header).is_err() {
+                    continue;
+                }
+                let len = u32::from_ne_bytes(header[6..10].try_into().unwrap()) as usize;
+                let kind = u32::from_ne_bytes(header[10..14].try_into().unwrap());
+                let mut payload = vec![0u8; len];
+                stream.read_exact(&mut payload).unwrap();
+
+                match kind {
+                    GET_TREE => {
+                        if let Some(tree) = trees.next() {
+                            last_tree = tree;
+                        }
+                        send(&mut stream, GET_TREE, last_tree.to_string().as_bytes()).unwrap();
+                    }
+                    SUBSCRIBE => {
+                        let script = script.take().unwrap_or_default();
+                        let steps = steps.take();
+                        std::thread::spawn(move || {
+                            send(&mut stream, SUBSCRIBE, br#"{"success": true}"#).unwrap();
+                            for batch in script {
+                                if steps.as_ref().is_none_or(|steps| steps.recv().is_err()) {
+                                    return;
+                                }
+                                for (kind, event) in batch {
+                                    send(&mut stream, kind, event.to_string().as_bytes()).unwrap();
+                                }
+                            }
+                            // 保持连接打开，直到提供者关闭
+                            let _ = stream.read(&mut [0u8; 1]);
+                        });
+                    }
+                    _ => unreachable!(),
+                }
+            }
+        });
+        (socket, step)
+    }
+
+    fn notifier() -> (FocusNotifier, Receiver<()>) {
+        let (tx, rx) = channel();
+        (Arc::new(move || { let _ = tx.send(()); }), rx)
+    }
+
+    #[test]
+    fn finds_focused_window_in_tree() {
+        assert_eq!(focused_pid(&tree(Some(200))), 200);
+        assert_eq!(focused_pid(&tree(Some(300))), 300);
+        assert_eq!(focused_pid(&tree(None)), 0);
+    }
+
+    #[test]
+    fn reads_initial_focus_and_follows_window_events() {
+        let script = vec![vec![
+            (EVENT_WINDOW, json!({"change": "title", "container": {"pid": 999}})),
+            (EVENT_WINDOW, json!({"change": "focus", "container": {"pid": 200}})),
+            (EVENT_WINDOW, json!({"change": "focus", "container": {"pid": 300}})),
+        ]];
+        let (socket, step) = fake_sway("window", vec![tree(Some(100))], script);
+        let (notify, rx) = notifier();
+
+        let provider = SwayProvider::connect(socket.clone(), notify).unwrap();
+        assert_eq!(provider.foreground_pid(), 100);
+
+        step.send(()).unwrap();
+        rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(provider.foreground_pid(), 300);
+        // 非焦点事件不会触发通知
+        assert!(rx.recv_timeout(Duration::from_millis(100)).is_err());
+
+        drop(provider);
+        let _ = std::fs::remove_file(socket);
+    }
+
+    #[test]
+    fn workspace_focus_rereads_the_tree() {
+        let script = vec![vec![(EVENT_WORKSPACE, json!({"change": "focus"}))]];
+        let (socket, step) = fake_sway("workspace", vec![tree(Some(100)), tree(None)], script);
+        let (notify, rx) = notifier();
+
+        let provider = SwayProvider::connect(socket.clone(), notify).unwrap();
+        assert_eq!(provider.foreground_pid(), 100);
+        step.send(()).unwrap();
+        rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        // 切到空工作区后没有窗口获得焦点
+        assert_eq!(provider.foreground_pid(), 0);
+
+        drop(provider);
+        let _ = std::fs::remove_file(socket);
+    }
+}
@@ -0,0 +1,115 @@
+use std::sync::Mutex;
+use std::sync::mpsc::channel;
+use std::thread::JoinHandle;
+use windows::Win32::Foundation::*;
+use windows::Win32::System::Threading::GetCurrentThreadId;
+use windows::Win32::UI::Accessibility::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use super::{FocusNotifier, ForegroundProvider};
+use crate::utils;
+
+// WinEvent 回调没有用户参数，只能通过全局变量把事件转发出去
+static EVENT_SENDER: Mutex<Option<FocusNotifier>> = Mutex::new(None);
+
+unsafe extern "system" fn win_event_callback(
+    _: HWINEVENTHOOK,
+    _: u32,
+    _: HWND,
+    _: i32,
+    _: i32,
+    _: u32,
+    _: u32,
+) {
+    if let Some(notify) = EVENT_SENDER.lock().unwrap().as_ref() {
+        notify();
+    }
+}
+
+// 只关心顶层窗口自身的标题变化，忽略控件与子对象的名称变化
+unsafe extern "system" fn name_change_callback(
+    hook: HWINEVENTHOOK,
+    event: u32,
+    hwnd: HWND,
+    id_object: i32,
+    id_child: i32,
+    thread: u32,
+    time: u32,
+) {
+    let top_level = unsafe { GetAncestor(hwnd, GA_ROOT) } == hwnd;
+    if id_object == OBJID_WINDOW.0 && id_child == CHILDID_SELF as i32 && top_level {
+        unsafe { win_event_callback(hook, event, hwnd, id_object, id_child, thread, time) };
+    }
+}
+
+/// 通过 SetWinEventHook 监听 EVENT_SYSTEM_FOREGROUND 的前台窗口提供者。
+/// 同时监听顶层窗口的 EVENT_OBJECT_NAMECHANGE，让按标题匹配的规则随标题变化及时生效。
+/// 钩子安装在独立的消息循环线程上，因此不依赖 UI 线程处理消息。
+pub struct WinEventProvider {
+    thread_id: u32,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl WinEventProvider {
+    pub fn new(notify: FocusNotifier) -> Self {
+        *EVENT_SENDER.lock().unwrap() = Some(notify);
+
+        let (tx, rx) = channel();
+        let thread = std::thread::spawn(move || unsafe {
+            let mut msg = MSG::default();
+            // 确保线程消息队列已创建，之后 PostThreadMessageW 才能送达
+            let _ = PeekMessageW(&mut msg, None, WM_USER, WM_USER, PM_NOREMOVE);
+            let hook = SetWinEventHook(
+                EVENT_SYSTEM_FOREGROUND,
+                EVENT_SYSTEM_FOREGROUND,
+                None,
+                Some(win_event_callback),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            );
+            let name_hook = SetWinEventHook(
+                EVENT_OBJECT_NAMECHANGE,
+                EVENT_OBJECT_NAMECHANGE,
+                None,
+                Some(name_change_callback),
+                0,
+                0,
+                WINEVENT_OUTOFCONTEXT,
+            );
+            let _ = tx.send(GetCurrentThreadId());
+
+            while GetMessageW(&mut msg, None, 0, 0).as_bool() {
+                let _ = TranslateMessage(&msg);
+                DispatchMessageW(&msg);
+            }
+
+            for hook in [hook, name_hook] {
+                if !hook.is_invalid() {
+                    let _ = UnhookWinEvent(hook);
+                }
+            }
+        });
+        let thread_id = rx.recv().unwrap_or(0);
+
+        Self { thread_id, thread: Some(thread) }
+    }
+}
+
+impl ForegroundProvider for WinEventProvider {
+    fn foreground_pid(&self) -> u32 {
+        utils::get_foreground_pid()
+    }
+}
+
+impl Drop for WinEventProvider {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = PostThreadMessageW(self.thread_id, WM_QUIT, WPARAM(0), LPARAM(0));
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+        *EVENT_SENDER.lock().unwrap() = None;
+    }
+}
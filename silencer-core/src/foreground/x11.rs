@@ -0,0 +1,165 @@
+use std::io;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::thread::JoinHandle;
+
+use x11rb::connection::Connection;
+use x11rb::protocol::Event;
+use x11rb::protocol::xproto::*;
+use x11rb::rust_connection::RustConnection;
+
+use super::{FocusNotifier, ForegroundProvider};
+
+struct Atoms {
+    net_active_window: Atom,
+    net_wm_pid: Atom,
+    // 仅用于在退出时唤醒阻塞在 wait_for_event 上的监听线程
+    wake: Atom,
+}
+
+/// 基于 EWMH 的 X11 前台窗口提供者：读取根窗口的 `_NET_ACTIVE_WINDOW` 与活动窗口的 `_NET_WM_PID`，
+/// 并订阅根窗口的 PropertyNotify，在活动窗口变化时更新 PID 并发出通知。
+pub struct X11Provider {
+    conn: Arc<RustConnection>,
+    root: Window,
+    wake: Atom,
+    pid: Arc<AtomicU32>,
+    stop: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+fn to_io<E: std::error::Error + Send + Sync + 'static>(e: E) -> io::Error {
+    io::Error::other(e)
+}
+
+fn intern(conn: &RustConnection, name: &[u8]) -> io::Result<Atom> {
+    Ok(conn.intern_atom(false, name).map_err(to_io)?.reply().map_err(to_io)?.atom)
+}
+
+fn read_cardinal(conn: &RustConnection, window: Window, property: Atom, kind: AtomEnum) -> Option<u32> {
+    let reply = conn.get_property(false, window, property, kind, 0, 1).ok()?.reply().ok()?;
+    reply.value32()?.next()
+}
+
+fn query_active_pid(conn: &RustConnection, root: Window, atoms: &Atoms) -> u32 {
+    let window = match read_cardinal(conn, root, atoms.net_active_window, AtomEnum::WINDOW) {
+        Some(window) if window != x11rb::NONE => window,
+        _ => return 0,
+    };
+    read_cardinal(conn, window, atoms.net_wm_pid, AtomEnum::CARDINAL).unwrap_or(0)
+}
+
+impl X11Provider {
+    /// 连接 `$DISPLAY` 指定的 X 服务器
+    pub fn connect(notify: FocusNotifier) -> io::Result<Self> {
+        let (conn, screen) = x11rb::connect(None).map_err(to_io)?;
+        let conn = Arc::new(conn);
+        let root = conn.setup().roots[screen].root;
+        let atoms = Atoms {
+            net_active_window: intern(&conn, b"_NET_ACTIVE_WINDOW")?,
+            net_wm_pid: intern(&conn, b"_NET_WM_PID")?,
+            wake: intern(&conn, b"_SILENCER_WAKE")?,
+        };
+
+        let attributes = ChangeWindowAttributesAux::new().event_mask(EventMask::PROPERTY_CHANGE);
+        conn.change_window_attributes(root, &attributes).map_err(to_io)?.check().map_err(to_io)?;
+
+        let pid = Arc::new(AtomicU32::new(query_active_pid(&conn, root, &atoms)));
+        let stop = Arc::new(AtomicBool::new(false));
+        let wake = atoms.wake;
+
+        let thread = {
+            let conn = Arc::clone(&conn);
+            let pid = Arc::clone(&pid);
+            let stop = Arc::clone(&stop);
+            std::thread::spawn(move || {
+                while let Ok(event) = conn.wait_for_event() {
+                    if stop.load(Ordering::SeqCst) {
+                        break;
+                    }
+                    if let Event::PropertyNotify(e) = event
+                        && e.window == root
+                        && e.atom == atoms.net_active_window
+                    {
+                        pid.store(query_active_pid(&conn, root, &atoms), Ordering::SeqCst);
+                        notify();
+                    }
+                }
+            })
+        };
+
+        Ok(Self { conn, root, wake, pid, stop, thread: Some(thread) })
+    }
+}
+
+impl ForegroundProvider for X11Provider {
+    fn foreground_pid(&self) -> u32 {
+        self.pid.load(Ordering::SeqCst)
+    }
+}
+
+impl Drop for X11Provider {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        let wake = ClientMessageEvent::new(32, self.root, self.wake, [0u32; 5]);
+        let sent = self.conn.send_event(false, self.root, EventMask::PROPERTY_CHANGE, wake).is_ok()
+            && self.conn.flush().is_ok();
+        if let Some(thread) = self.thread.take() {
+            // 连接已断开时线程会因 wait_for_event 出错而自行退出
+            if sent {
+                let _ = thread.join();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::channel;
+    use x11rb::wrapper::ConnectionExt as _;
+    use std::time::Duration;
+
+    /// 需要 X 服务器，例如：
+    ///
+    /// ```sh
+    /// Xvfb :99 & DISPLAY=:99 cargo test -- --ignored x11
+    /// ```
+    ///
+    /// 测试自己扮演一个最小的窗口管理器：创建窗口、设置 `_NET_WM_PID`，再改写根窗口的 `_NET_ACTIVE_WINDOW`。
+    #[test]
+    #[ignore = "需要 X11 显示服务器（如 Xvfb）"]
+    fn follows_net_active_window_changes() {
+        let (tx, rx) = channel();
+        let provider = X11Provider::connect(Arc::new(move || {
+            let _ = tx.send(());
+        }))
+        .expect("无法连接 X 服务器");
+
+        let (conn, screen) = x11rb::connect(None).unwrap();
+        let root = conn.setup().roots[screen].root;
+        let net_active_window = intern(&conn, b"_NET_ACTIVE_WINDOW").unwrap();
+        let net_wm_pid = intern(&conn, b"_NET_WM_PID").unwrap();
+
+        let mut windows = Vec::new();
+        for pid in [4242u32, 4343] {
+            let window = conn.generate_id().unwrap();
+            conn.create_window(0, window, root, 0, 0, 10, 10, 0, WindowClass::INPUT_OUTPUT, 0, &CreateWindowAux::new())
+                .unwrap();
+            conn.change_property32(PropMode::REPLACE, window, net_wm_pid, AtomEnum::CARDINAL, &[pid]).unwrap();
+            windows.push((window, pid));
+        }
+
+        for (window, pid) in windows {
+            conn.change_property32(PropMode::REPLACE, root, net_active_window, AtomEnum::WINDOW, &[window]).unwrap();
+            conn.flush().unwrap();
+            rx.recv_timeout(Duration::from_secs(2)).expect("没有收到前台切换通知");
+            assert_eq!(provider.foreground_pid(), pid);
+        }
+
+        conn.change_property32(PropMode::REPLACE, root, net_active_window, AtomEnum::WINDOW, &[x11rb::NONE]).unwrap();
+        conn.flush().unwrap();
+        rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(provider.foreground_pid(), 0);
+    }
+}
@@ -0,0 +1,169 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::audio::{SessionState, VOLUME_TOLERANCE};
+use crate::config::BackgroundAction;
+
+/// 日志中的一条记录：一个被我们修改过、尚未恢复的会话
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct JournalEntry {
+    pub device_id: String,
+    pub session_id: String,
+    pub pid: u32,
+    pub process_name: String,
+    /// 进程启动时间（Unix 毫秒），与 PID 一起识别进程，防止 PID 被复用后误认
+    pub process_start_ms: Option<u64>,
+    /// 修改前的原始状态
+    pub muted: bool,
+    pub volume: f32,
+    pub volume_applied: bool,
+    /// 最近一次对会话执行的动作，渐变进行中或旧版本的日志中为 None
+    #[serde(default)]
+    pub applied: Option<BackgroundAction>,
+}
+
+impl JournalEntry {
+    pub fn original_state(&self) -> SessionState {
+        SessionState { muted: self.muted, volume: self.volume }
+    }
+
+    /// `state` 是否仍是执行 [`JournalEntry::applied`] 后的状态，不是说明之后被用户修改过
+    pub fn shows_applied(&self, state: SessionState) -> bool {
+        match self.applied {
+            Some(BackgroundAction::Mute) => state.muted,
+            Some(action) => {
+                !state.muted && action.volume(self.volume).is_some_and(|volume| (state.volume - volume).abs() < VOLUME_TOLERANCE)
+            }
+            None => false,
+        }
+    }
+}
+
+/// 启动时根据日志修复的一个会话
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repair {
+    pub process_name: String,
+    pub pid: u32,
+    pub device_id: String,
+    /// 恢复后的状态
+    pub restored: SessionState,
+}
+
+/// 持久化的静音日志：每次修改或恢复会话后重写，程序异常退出后下次启动时据此修复会话
+pub struct Journal {
+    path: PathBuf,
+}
+
+impl Journal {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// 默认的日志位置：Windows 下为 %LOCALAPPDATA%\silencer-rs，其它平台为 $XDG_STATE_HOME/silencer-rs（默认 ~/.local/state）
+    pub fn default_path() -> Option<PathBuf> {
+        let dir = if cfg!(windows) {
+            std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+        } else {
+            std::env::var_os("XDG_STATE_HOME")
+                .map(PathBuf::from)
+                .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("state")))
+        }?;
+        Some(dir.join("silencer-rs").join("journal.json"))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 读取日志，文件不存在或已损坏时返回空列表
+    pub fn load(&self) -> Vec<JournalEntry> {
+        fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// 重写日志；没有记录时删除文件。先写临时文件再重命名，避免写到一半时崩溃留下损坏的日志
+    pub fn store(&self, entries: &[JournalEntry]) -> io::Result<()> {
+        if entries.is_empty() {
+            return match fs::remove_file(&self.path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let tmp = self.path.with_extension("json.tmp");
+        let content = serde_json::to_string_pretty(entries).map_err(io::Error::other)?;
+        fs::write(&tmp, content)?;
+        fs::rename(&tmp, &self.path)
+    }
+}
+
+/// 进程启动时间转换为日志中使用的 Unix 毫秒
+pub fn unix_ms(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH).ok().map(|d| d.as_millis() as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pid: u32) -> JournalEntry {
+        JournalEntry {
+            device_id: "speakers".to_string(),
+            session_id: format!("session-{}", pid),
+            pid,
+            process_name: "game.exe".to_string(),
+            process_start_ms: Some(1_700_000_000_000),
+            muted: false,
+            volume: 0.5,
+            volume_applied: false,
+            applied: None,
+        }
+    }
+
+    fn journal(name: &str) -> Journal {
+        let dir = std::env::temp_dir().join(format!("silencer-journal-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&dir);
+        Journal::new(dir.join("nested").join("journal.json"))
+    }
+
+    #[test]
+    fn round_trips_entries() {
+        let journal = journal("round-trip");
+        journal.store(&[entry(1), entry(2)]).unwrap();
+
+        assert_eq!(journal.load(), [entry(1), entry(2)]);
+        assert!(!journal.path().with_extension("json.tmp").exists());
+        let _ = fs::remove_dir_all(journal.path().parent().unwrap().parent().unwrap());
+    }
+
+    #[test]
+    fn storing_nothing_removes_the_file() {
+        let journal = journal("empty");
+        journal.store(&[]).unwrap();
+        journal.store(&[entry(1)]).unwrap();
+        assert!(journal.path().exists());
+
+        journal.store(&[]).unwrap();
+        assert!(!journal.path().exists());
+        assert!(journal.load().is_empty());
+        let _ = fs::remove_dir_all(journal.path().parent().unwrap().parent().unwrap());
+    }
+
+    #[test]
+    fn corrupt_journal_loads_as_empty() {
+        let journal = journal("corrupt");
+        fs::create_dir_all(journal.path().parent().unwrap()).unwrap();
+        fs::write(journal.path(), "{ not json").unwrap();
+
+        assert!(journal.load().is_empty());
+        let _ = fs::remove_dir_all(journal.path().parent().unwrap().parent().unwrap());
+    }
+}
@@ -0,0 +1,22 @@
+//! Silencer 的静音引擎：配置、静音决策、防抖/周期同步调度以及音频后端与前台窗口的抽象。
+//! 不依赖任何图形界面库，可以嵌入到其它前端中使用。
+
+pub mod audio;
+pub mod clock;
+pub mod config;
+pub mod decision;
+pub mod engine;
+pub mod fade;
+pub mod foreground;
+pub mod journal;
+pub mod pattern;
+pub mod process;
+pub mod scheduler;
+pub mod simulator;
+pub mod worker;
+#[cfg(windows)]
+mod utils;
+
+pub use config::Config;
+pub use engine::Engine;
+pub use worker::{Command, Snapshot, Worker};
@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock, RwLock};
+
+use regex::{Regex, RegexBuilder};
+
+use crate::audio::AudioSessionInfo;
+
+/// 按可执行文件完整路径匹配的条目前缀，以路径分隔符结尾时匹配该目录下的所有程序
+pub const PATH_PREFIX: &str = "path:";
+/// 按可执行文件版本信息中的公司名称匹配的条目前缀
+pub const COMPANY_PREFIX: &str = "company:";
+/// 按可执行文件版本信息中的产品名称匹配的条目前缀
+pub const PRODUCT_PREFIX: &str = "product:";
+/// 按进程主窗口标题匹配的条目前缀
+pub const TITLE_PREFIX: &str = "title:";
+
+/// 列表条目的匹配方式，均不区分大小写（Linux 下的路径除外）：
+/// - `path:路径`：可执行文件的完整路径，例如 `path:D:\Games\launcher.exe`；以 `\` 或 `/` 结尾时匹配该目录及其子目录下的所有程序
+/// - `company:名称` / `product:名称`：可执行文件版本信息中的公司或产品名称，例如 `company:Valve`
+/// - `title:文本`：主窗口标题包含该文本，例如 `title:YouTube`；`title:/模式/` 为在标题中查找的正则表达式（不加锚点）
+/// - `/模式/`：正则表达式，匹配整个进程名，自动加上首尾锚点，例如 `/game-win64-.*\.exe/`
+/// - 含 `*` 或 `?` 的条目：通配符，`*` 匹配任意多个字符，`?` 匹配单个字符，例如 `*steam*`
+/// - 其它条目：进程名
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    /// 小写的进程名
+    Name(String),
+    Glob(Regex),
+    Regex(Regex),
+    /// 规范化后的完整路径
+    Path(String),
+    /// 规范化后的目录，以分隔符结尾
+    Directory(String),
+    /// 小写的公司名称
+    Company(String),
+    /// 小写的产品名称
+    Product(String),
+    /// 标题中应包含的小写文本
+    Title(String),
+    TitleRegex(Regex),
+}
+
+/// 条目中的正则表达式或通配符无法编译，或路径、名称为空
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternError {
+    pub entry: String,
+    pub message: String,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "无效的模式 {}: {}", self.entry, self.message)
+    }
+}
+
+impl std::error::Error for PatternError {}
+
+/// Windows 下路径不区分大小写，且 `/` 与 `\` 等价
+fn normalize_path(path: &str) -> String {
+    if cfg!(windows) {
+        path.replace('/', "\\").to_lowercase()
+    } else {
+        path.to_string()
+    }
+}
+
+impl Pattern {
+    pub fn parse(entry: &str) -> Result<Self, PatternError> {
+        let error = |message: String| PatternError { entry: entry.to_string(), message };
+        let compile = |source: &str| {
+            RegexBuilder::new(&format!("^(?:{})$", source))
+                .case_insensitive(true)
+                .build()
+                .map_err(|e| error(e.to_string()))
+        };
+        let value = |prefix: &str| {
+            let value = entry.strip_prefix(prefix)?.trim();
+            Some(if value.is_empty() { Err(error("内容不能为空".to_string())) } else { Ok(value) })
+        };
+
+        if let Some(path) = value(PATH_PREFIX) {
+            let path = normalize_path(path?);
+            return Ok(if path.ends_with(['/', '\\']) { Pattern::Directory(path) } else { Pattern::Path(path) });
+        }
+        if let Some(company) = value(COMPANY_PREFIX) {
+            return Ok(Pattern::Company(company?.to_lowercase()));
+        }
+        if let Some(product) = value(PRODUCT_PREFIX) {
+            return Ok(Pattern::Product(product?.to_lowercase()));
+        }
+        if let Some(title) = value(TITLE_PREFIX) {
+            let title = title?;
+            return match title.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+                Some(source) => RegexBuilder::new(source)
+                    .case_insensitive(true)
+                    .build()
+                    .map(Pattern::TitleRegex)
+                    .map_err(|e| error(e.to_string())),
+                None => Ok(Pattern::Title(title.to_lowercase())),
+            };
+        }
+        if let Some(source) = entry.strip_prefix('/').and_then(|rest| rest.strip_suffix('/')) {
+            return compile(source).map(Pattern::Regex);
+        }
+        if entry.contains(['*', '?']) {
+            let source: String = entry
+                .chars()
+                .map(|c| match c {
+                    '*' => ".*".to_string(),
+                    '?' => ".".to_string(),
+                    c => regex::escape(&c.to_string()),
+                })
+                .collect();
+            return compile(&source).map(Pattern::Glob);
+        }
+        Ok(Pattern::Name(entry.to_lowercase()))
+    }
+
+    /// 条目是否只是单个进程名（其它模式可能命中多个不同的进程）
+    pub fn is_name(&self) -> bool {
+        matches!(self, Pattern::Name(_))
+    }
+
+    pub fn matches(&self, session: &AudioSessionInfo) -> bool {
+        let lower = |value: &Option<String>| value.as_ref().map(|value| value.trim().to_lowercase());
+        match self {
+            Pattern::Name(expected) => *expected == session.name.to_lowercase(),
+            Pattern::Glob(regex) | Pattern::Regex(regex) => regex.is_match(&session.name),
+            Pattern::Path(expected) => session.exe_path.as_deref().is_some_and(|path| normalize_path(path) == *expected),
+            Pattern::Directory(dir) => session.exe_path.as_deref().is_some_and(|path| normalize_path(path).starts_with(dir.as_str())),
+            Pattern::Company(expected) => lower(&session.company_name).as_ref() == Some(expected),
+            Pattern::Product(expected) => lower(&session.product_name).as_ref() == Some(expected),
+            Pattern::Title(text) => session.window_title.to_lowercase().contains(text.as_str()),
+            Pattern::TitleRegex(regex) => regex.is_match(&session.window_title),
+        }
+    }
+}
+
+// 编译结果按条目文本缓存，同一条目只编译一次；无效的条目记为 None
+fn cache() -> &'static RwLock<HashMap<String, Option<Arc<Pattern>>>> {
+    static CACHE: OnceLock<RwLock<HashMap<String, Option<Arc<Pattern>>>>> = OnceLock::new();
+    CACHE.get_or_init(Default::default)
+}
+
+/// 编译并缓存条目，供后续 [`compiled`] 直接使用
+pub fn compile(entry: &str) -> Result<Arc<Pattern>, PatternError> {
+    let result = Pattern::parse(entry).map(Arc::new);
+    cache().write().unwrap().insert(entry.to_string(), result.as_ref().ok().cloned());
+    result
+}
+
+/// 条目编译后的模式，无效的条目返回 None（不命中任何会话）
+pub fn compiled(entry: &str) -> Option<Arc<Pattern>> {
+    if let Some(pattern) = cache().read().unwrap().get(entry) {
+        return pattern.clone();
+    }
+    compile(entry).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn named(name: &str) -> AudioSessionInfo {
+        AudioSessionInfo { name: name.to_string(), ..Default::default() }
+    }
+
+    fn matches(entry: &str, name: &str) -> bool {
+        Pattern::parse(entry).unwrap().matches(&named(name))
+    }
+
+    #[test]
+    fn plain_entries_match_the_whole_name_ignoring_case() {
+        assert!(matches("Game.exe", "game.EXE"));
+        assert!(!matches("game.exe", "game.exe.bak"));
+        assert!(Pattern::parse("game.exe").unwrap().is_name());
+    }
+
+    #[test]
+    fn globs_match_helpers_and_versioned_names() {
+        assert!(matches("*steam*", "steamwebhelper.exe"));
+        assert!(matches("*steam*", "Steam.exe"));
+        assert!(matches("Game-*-Shipping.exe", "Game-Win64-Shipping.exe"));
+        assert!(matches("chrome?.exe", "chrome2.exe"));
+        assert!(!matches("chrome?.exe", "chrome.exe"));
+        // 点号等正则元字符按字面匹配
+        assert!(!matches("*.exe", "gameXexe"));
+    }
+
+    #[test]
+    fn regexes_are_anchored() {
+        assert!(matches("/msedge(webview2)?\\.exe/", "msedgewebview2.exe"));
+        assert!(matches("/msedge(webview2)?\\.exe/", "MSEdge.exe"));
+        assert!(!matches("/edge/", "msedge.exe"));
+        assert!(!Pattern::parse("/edge/").unwrap().is_name());
+    }
+
+    #[test]
+    fn invalid_regex_is_rejected_and_never_matches() {
+        let error = Pattern::parse("/game(/").unwrap_err();
+        assert_eq!(error.entry, "/game(/");
+        assert!(compile("/game(/").is_err());
+        assert!(compiled("/game(/").is_none());
+    }
+
+    #[test]
+    fn compiled_patterns_are_cached() {
+        let first = compile("*cached*").unwrap();
+        assert!(Arc::ptr_eq(&first, &compiled("*cached*").unwrap()));
+    }
+
+    fn executable(name: &str, path: &str, company: Option<&str>, product: Option<&str>) -> AudioSessionInfo {
+        AudioSessionInfo {
+            exe_path: Some(path.to_string()),
+            company_name: company.map(str::to_string),
+            product_name: product.map(str::to_string),
+            ..named(name)
+        }
+    }
+
+    #[test]
+    fn path_entries_tell_same_named_programs_apart() {
+        let game = executable("launcher.exe", "/games/shooter/launcher.exe", None, None);
+        let tool = executable("launcher.exe", "/tools/launcher.exe", None, None);
+        let pattern = Pattern::parse("path:/games/shooter/launcher.exe").unwrap();
+
+        assert!(pattern.matches(&game));
+        assert!(!pattern.matches(&tool));
+        assert!(!pattern.matches(&named("launcher.exe")));
+    }
+
+    #[test]
+    fn directory_entries_match_everything_below() {
+        let pattern = Pattern::parse("path:/games/").unwrap();
+
+        assert!(pattern.matches(&executable("a.exe", "/games/shooter/bin/a.exe", None, None)));
+        assert!(!pattern.matches(&executable("b.exe", "/gamesave/b.exe", None, None)));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_paths_ignore_case_and_separator_style() {
+        let pattern = Pattern::parse("path:D:/Games/").unwrap();
+        assert!(pattern.matches(&executable("a.exe", "d:\\games\\a.exe", None, None)));
+    }
+
+    #[test]
+    fn publisher_entries_match_version_info() {
+        let steam = executable("steam.exe", "/steam/steam.exe", Some("Valve Corporation"), Some("Steam"));
+
+        assert!(Pattern::parse("company:valve corporation").unwrap().matches(&steam));
+        assert!(Pattern::parse("product:STEAM").unwrap().matches(&steam));
+        assert!(!Pattern::parse("company:Valve").unwrap().matches(&steam));
+        assert!(!Pattern::parse("company:Valve Corporation").unwrap().matches(&named("steam.exe")));
+    }
+
+    #[test]
+    fn empty_prefixed_entries_are_rejected() {
+        assert!(Pattern::parse("path:").is_err());
+        assert!(Pattern::parse("company:  ").is_err());
+        assert!(Pattern::parse("title:").is_err());
+    }
+
+    fn titled(name: &str, title: &str) -> AudioSessionInfo {
+        AudioSessionInfo { window_title: title.to_string(), ..named(name) }
+    }
+
+    #[test]
+    fn title_entries_search_the_window_title() {
+        let youtube = titled("chrome.exe", "Lofi beats - YouTube - Google Chrome");
+        let docs = titled("chrome.exe", "Quarterly report - Google Docs - Google Chrome");
+
+        let text = Pattern::parse("title:youtube").unwrap();
+        assert!(text.matches(&youtube));
+        assert!(!text.matches(&docs));
+        assert!(!text.matches(&named("chrome.exe")));
+
+        let regex = Pattern::parse("title:/ - (YouTube|Twitch) - /").unwrap();
+        assert!(regex.matches(&youtube));
+        assert!(!regex.matches(&docs));
+        assert!(Pattern::parse("title:/(/").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+pub mod cache;
+pub mod fake;
+#[cfg(target_os = "linux")]
+pub mod procfs;
+pub mod tree;
+#[cfg(windows)]
+pub mod win32;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessKind {
+    Normal,
+    /// 已退出但尚未被父进程回收
+    Zombie,
+    /// 内核线程，没有可执行文件和命令行
+    KernelThread,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessInfo {
+    pub pid: u32,
+    /// 可执行文件名，例如 "firefox" 或 "game.exe"
+    pub name: String,
+    /// 可执行文件完整路径，权限不足或进程没有可执行文件时为 None
+    pub exe_path: Option<PathBuf>,
+    pub cmdline: Vec<String>,
+    pub parent_pid: Option<u32>,
+    pub start_time: Option<SystemTime>,
+    pub kind: ProcessKind,
+    /// 可执行文件版本信息中的公司名称（Windows 的 CompanyName），其它平台为 None
+    pub company_name: Option<String>,
+    /// 可执行文件版本信息中的产品名称（Windows 的 ProductName），其它平台为 None
+    pub product_name: Option<String>,
+}
+
+/// 进程在进程树中的位置
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TreeNode {
+    pub parent_pid: Option<u32>,
+    /// 进程组 ID（Linux 的 pgid），无法获取时为 None
+    pub group_id: Option<u32>,
+    pub start_time: Option<SystemTime>,
+}
+
+/// 进程信息提供者：根据 PID 查询进程身份
+pub trait ProcessInfoProvider {
+    /// 进程不存在时返回 None；权限不足时尽量返回能读到的部分
+    fn process_info(&self, pid: u32) -> Option<ProcessInfo>;
+
+    /// 进程启动时间，与 PID 一起唯一标识一个进程。实现应比 [`ProcessInfoProvider::process_info`] 更轻量
+    fn start_time(&self, pid: u32) -> Option<SystemTime> {
+        self.process_info(pid)?.start_time
+    }
+
+    /// 进程的父进程与进程组，用于判断进程间的关系。实现应比 [`ProcessInfoProvider::process_info`] 更轻量
+    fn tree_node(&self, pid: u32) -> Option<TreeNode> {
+        let info = self.process_info(pid)?;
+        Some(TreeNode { parent_pid: info.parent_pid, group_id: None, start_time: info.start_time })
+    }
+
+    /// 一次性读取所有进程的父进程（PID → 父进程 PID）。逐个查询父进程代价较高的平台应实现它，
+    /// [`tree::ProcessTree`] 每次构建只读取一次；返回 None 时按需调用 [`ProcessInfoProvider::tree_node`]。
+    /// 通过它得到的进程没有进程组
+    fn parent_pids(&self) -> Option<HashMap<u32, u32>> {
+        None
+    }
+
+    /// 桌面外壳进程（例如 Windows 的 explorer.exe），大多数程序都由它启动，进程树不把它视为祖先
+    fn shell_pid(&self) -> Option<u32> {
+        None
+    }
+
+    /// 进程名，无法获取时退回到 "进程 (pid)"
+    fn process_name(&self, pid: u32) -> String {
+        if pid == 0 {
+            return "System".to_string();
+        }
+        self.process_info(pid)
+            .map(|info| info.name)
+            .unwrap_or_else(|| format!("进程 ({})", pid))
+    }
+}
+
+/// 返回当前平台的进程信息提供者
+pub fn platform_provider() -> Box<dyn ProcessInfoProvider> {
+    #[cfg(windows)]
+    {
+        Box::new(win32::Win32ProcessInfo)
+    }
+    #[cfg(target_os = "linux")]
+    {
+        Box::new(procfs::ProcProcessInfo::new())
+    }
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        struct Unavailable;
+        impl ProcessInfoProvider for Unavailable {
+            fn process_info(&self, _pid: u32) -> Option<ProcessInfo> {
+                None
+            }
+        }
+        Box::new(Unavailable)
+    }
+}
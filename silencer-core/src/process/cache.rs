@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+use super::{ProcessInfo, ProcessInfoProvider};
+
+/// 按 (PID, 进程启动时间) 缓存的进程身份。PID 被复用后启动时间不同，不会拿到旧进程的信息。
+/// 每次查询只需读取启动时间，完整信息只在进程第一次出现时查询一次
+pub struct ProcessCache {
+    provider: Box<dyn ProcessInfoProvider>,
+    entries: HashMap<(u32, Option<SystemTime>), ProcessInfo>,
+}
+
+impl ProcessCache {
+    pub fn new(provider: Box<dyn ProcessInfoProvider>) -> Self {
+        Self { provider, entries: HashMap::new() }
+    }
+
+    /// 查询进程身份，进程不存在时返回 None
+    pub fn lookup(&mut self, pid: u32) -> Option<&ProcessInfo> {
+        let key = (pid, self.provider.start_time(pid));
+        if !self.entries.contains_key(&key) {
+            // 同一 PID 的旧记录属于已退出的进程
+            self.entries.retain(|(cached, _), _| *cached != pid);
+            let info = self.provider.process_info(pid)?;
+            self.entries.insert(key, info);
+        }
+        self.entries.get(&key)
+    }
+
+    /// 进程名，无法获取时退回到 "进程 (pid)"
+    pub fn name(&mut self, pid: u32) -> String {
+        if pid == 0 {
+            return "System".to_string();
+        }
+        self.lookup(pid).map(|info| info.name.clone()).unwrap_or_else(|| format!("进程 ({})", pid))
+    }
+
+    /// 丢弃 `alive` 返回 false 的 PID 的记录，调用方通常传入仍有音频会话的进程
+    pub fn expire(&mut self, alive: impl Fn(u32) -> bool) {
+        self.entries.retain(|(pid, _), _| alive(*pid));
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::ProcessKind;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// 可在测试中替换进程的提供者，并记录完整查询的次数
+    #[derive(Clone, Default)]
+    struct Processes {
+        running: Arc<Mutex<HashMap<u32, (String, u64)>>>,
+        queries: Arc<Mutex<usize>>,
+    }
+
+    impl Processes {
+        fn start(&self, pid: u32, name: &str, start_ms: u64) {
+            self.running.lock().unwrap().insert(pid, (name.to_string(), start_ms));
+        }
+
+        fn exit(&self, pid: u32) {
+            self.running.lock().unwrap().remove(&pid);
+        }
+
+        fn queries(&self) -> usize {
+            *self.queries.lock().unwrap()
+        }
+    }
+
+    impl ProcessInfoProvider for Processes {
+        fn process_info(&self, pid: u32) -> Option<ProcessInfo> {
+            *self.queries.lock().unwrap() += 1;
+            let (name, start_ms) = self.running.lock().unwrap().get(&pid).cloned()?;
+            Some(ProcessInfo {
+                pid,
+                name,
+                exe_path: None,
+                cmdline: Vec::new(),
+                parent_pid: None,
+                start_time: Some(SystemTime::UNIX_EPOCH + Duration::from_millis(start_ms)),
+                kind: ProcessKind::Normal,
+                company_name: None,
+                product_name: None,
+            })
+        }
+
+        fn start_time(&self, pid: u32) -> Option<SystemTime> {
+            let (_, start_ms) = self.running.lock().unwrap().get(&pid).cloned()?;
+            Some(SystemTime::UNIX_EPOCH + Duration::from_millis(start_ms))
+        }
+    }
+
+    #[test]
+    fn repeated_lookups_query_the_process_once() {
+        let processes = Processes::default();
+        processes.start(100, "game.exe", 5_000);
+        let mut cache = ProcessCache::new(Box::new(processes.clone()));
+
+        assert_eq!(cache.name(100), "game.exe");
+        assert_eq!(cache.name(100), "game.exe");
+        assert_eq!(processes.queries(), 1);
+    }
+
+    #[test]
+    fn reused_pid_is_not_mistaken_for_the_old_process() {
+        let processes = Processes::default();
+        processes.start(100, "game.exe", 5_000);
+        let mut cache = ProcessCache::new(Box::new(processes.clone()));
+        assert_eq!(cache.name(100), "game.exe");
+
+        processes.exit(100);
+        processes.start(100, "tool.exe", 9_000);
+        let info = cache.lookup(100).unwrap();
+        assert_eq!(info.name, "tool.exe");
+        assert_eq!(info.start_time, Some(SystemTime::UNIX_EPOCH + Duration::from_millis(9_000)));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn exited_processes_expire() {
+        let processes = Processes::default();
+        processes.start(100, "game.exe", 5_000);
+        processes.start(200, "music.exe", 6_000);
+        let mut cache = ProcessCache::new(Box::new(processes.clone()));
+        cache.lookup(100);
+        cache.lookup(200);
+
+        cache.expire(|pid| pid == 200);
+        assert_eq!(cache.len(), 1);
+
+        processes.exit(200);
+        assert!(cache.lookup(200).is_none());
+        assert_eq!(cache.name(200), "进程 (200)");
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,90 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use super::{ProcessInfo, ProcessInfoProvider, ProcessKind, TreeNode};
+
+/// 内存中的进程表，用于在测试中构造进程树。进程按加入顺序依次启动，保证父进程早于子进程
+#[derive(Default)]
+pub struct FakeProcesses {
+    processes: HashMap<u32, (String, TreeNode)>,
+    shell: Option<u32>,
+    // 是否像 Windows 一样一次性提供父进程表，以及被读取的次数
+    parent_table: bool,
+    parent_table_reads: Cell<usize>,
+}
+
+impl FakeProcesses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 加入一个进程，`parent_pid` 为 0 表示没有父进程
+    pub fn with(mut self, pid: u32, name: &str, parent_pid: u32) -> Self {
+        let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(self.processes.len() as u64 + 1);
+        let node = TreeNode { parent_pid: (parent_pid != 0).then_some(parent_pid), group_id: None, start_time: Some(start_time) };
+        self.processes.insert(pid, (name.to_string(), node));
+        self
+    }
+
+    /// 把该进程作为桌面外壳进程
+    pub fn with_shell(mut self, pid: u32) -> Self {
+        self.shell = Some(pid);
+        self
+    }
+
+    /// 一次性提供父进程表（见 [`ProcessInfoProvider::parent_pids`]）
+    pub fn with_parent_table(mut self) -> Self {
+        self.parent_table = true;
+        self
+    }
+
+    pub fn parent_table_reads(&self) -> usize {
+        self.parent_table_reads.get()
+    }
+
+    /// 设置已加入进程的进程组
+    pub fn with_group(mut self, pid: u32, group_id: u32) -> Self {
+        if let Some((_, node)) = self.processes.get_mut(&pid) {
+            node.group_id = Some(group_id);
+        }
+        self
+    }
+}
+
+impl ProcessInfoProvider for FakeProcesses {
+    fn process_info(&self, pid: u32) -> Option<ProcessInfo> {
+        let (name, node) = self.processes.get(&pid)?;
+        Some(ProcessInfo {
+            pid,
+            name: name.clone(),
+            exe_path: None,
+            cmdline: Vec::new(),
+            parent_pid: node.parent_pid,
+            start_time: node.start_time,
+            kind: ProcessKind::Normal,
+            company_name: None,
+            product_name: None,
+        })
+    }
+
+    fn start_time(&self, pid: u32) -> Option<SystemTime> {
+        self.processes.get(&pid)?.1.start_time
+    }
+
+    fn tree_node(&self, pid: u32) -> Option<TreeNode> {
+        self.processes.get(&pid).map(|(_, node)| *node)
+    }
+
+    fn parent_pids(&self) -> Option<HashMap<u32, u32>> {
+        if !self.parent_table {
+            return None;
+        }
+        self.parent_table_reads.set(self.parent_table_reads.get() + 1);
+        Some(self.processes.iter().map(|(&pid, (_, node))| (pid, node.parent_pid.unwrap_or(0))).collect())
+    }
+
+    fn shell_pid(&self) -> Option<u32> {
+        self.shell
+    }
+}
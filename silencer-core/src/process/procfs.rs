@@ -0,0 +1,342 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use super::{ProcessInfo, ProcessInfoProvider, ProcessKind, TreeNode};
+
+// include/linux/sched.h
+const PF_KTHREAD: u64 = 0x0020_0000;
+
+/// 基于 /proc 的进程信息提供者
+pub struct ProcProcessInfo {
+    root: PathBuf,
+    clock_ticks: u64,
+}
+
+/// /proc/[pid]/stat 中我们关心的字段
+#[derive(Debug, PartialEq)]
+struct Stat {
+    comm: String,
+    state: char,
+    ppid: u32,
+    pgrp: u32,
+    flags: u64,
+    start_ticks: u64,
+}
+
+/// 解析 /proc/[pid]/stat。comm 可能包含空格和括号，因此以最后一个 ')' 为界
+fn parse_stat(stat: &str) -> Option<Stat> {
+    let open = stat.find('(')?;
+    let close = stat.rfind(')')?;
+    let comm = stat.get(open + 1..close)?.to_string();
+    let fields: Vec<&str> = stat.get(close + 1..)?.split_whitespace().collect();
+    // 字段编号见 proc(5)：state 为第 3 个字段，ppid 第 4，pgrp 第 5，flags 第 9，starttime 第 22
+    Some(Stat {
+        comm,
+        state: fields.first()?.chars().next()?,
+        ppid: fields.get(1)?.parse().ok()?,
+        pgrp: fields.get(2)?.parse().ok()?,
+        flags: fields.get(6)?.parse().ok()?,
+        start_ticks: fields.get(19)?.parse().ok()?,
+    })
+}
+
+/// 时钟滴答数换算为时长。先按整秒相除，避免滴答数很大时乘法溢出
+fn ticks_to_duration(ticks: u64, clock_ticks: u64) -> Duration {
+    Duration::from_secs(ticks / clock_ticks) + Duration::from_nanos(ticks % clock_ticks * 1_000_000_000 / clock_ticks)
+}
+
+/// 从 /proc/stat 读取系统启动时间（btime，Unix 秒）
+fn parse_boot_time(stat: &str) -> Option<u64> {
+    stat.lines().find_map(|line| line.strip_prefix("btime ")?.trim().parse().ok())
+}
+
+fn file_name(path: &str) -> Option<String> {
+    Path::new(path).file_name().map(|name| name.to_string_lossy().into_owned())
+}
+
+impl ProcProcessInfo {
+    pub fn new() -> Self {
+        Self::with_root("/proc")
+    }
+
+    /// 使用其它目录作为 /proc（测试时用于构造假的进程树）
+    pub fn with_root(root: impl Into<PathBuf>) -> Self {
+        // SAFETY: sysconf 只读取系统配置
+        let ticks = unsafe { libc::sysconf(libc::_SC_CLK_TCK) };
+        Self { root: root.into(), clock_ticks: if ticks > 0 { ticks as u64 } else { 100 } }
+    }
+
+    fn boot_time(&self) -> Option<SystemTime> {
+        let btime = parse_boot_time(&fs::read_to_string(self.root.join("stat")).ok()?)?;
+        Some(SystemTime::UNIX_EPOCH + Duration::from_secs(btime))
+    }
+
+    fn start_time_of(&self, stat: &Stat) -> Option<SystemTime> {
+        self.boot_time().map(|boot| boot + ticks_to_duration(stat.start_ticks, self.clock_ticks))
+    }
+
+    fn exe_path(dir: &Path) -> Option<PathBuf> {
+        // 被删除或替换的可执行文件会带有 " (deleted)" 后缀
+        let target = fs::read_link(dir.join("exe")).ok()?;
+        let target = target.to_string_lossy();
+        Some(PathBuf::from(target.strip_suffix(" (deleted)").unwrap_or(&target)))
+    }
+
+    fn cmdline(dir: &Path) -> Vec<String> {
+        fs::read(dir.join("cmdline"))
+            .map(|raw| {
+                raw.split(|&b| b == 0)
+                    .filter(|arg| !arg.is_empty())
+                    .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+impl Default for ProcProcessInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessInfoProvider for ProcProcessInfo {
+    fn process_info(&self, pid: u32) -> Option<ProcessInfo> {
+        let dir = self.root.join(pid.to_string());
+        let stat = match fs::read_to_string(dir.join("stat")) {
+            Ok(stat) => parse_stat(&stat),
+            // hidepid 等挂载选项会拒绝读取，但进程仍然存在
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => None,
+            Err(_) => return None,
+        };
+        let Some(stat) = stat else {
+            return Some(ProcessInfo {
+                pid,
+                name: format!("进程 ({})", pid),
+                exe_path: None,
+                cmdline: Vec::new(),
+                parent_pid: None,
+                start_time: None,
+                kind: ProcessKind::Normal,
+                company_name: None,
+                product_name: None,
+            });
+        };
+
+        let kind = if stat.flags & PF_KTHREAD != 0 {
+            ProcessKind::KernelThread
+        } else if stat.state == 'Z' {
+            ProcessKind::Zombie
+        } else {
+            ProcessKind::Normal
+        };
+
+        let (exe_path, cmdline) = match kind {
+            ProcessKind::Normal => (Self::exe_path(&dir), Self::cmdline(&dir)),
+            // 僵尸进程和内核线程的 exe/cmdline 都不可读或为空
+            _ => (None, Vec::new()),
+        };
+
+        // 优先使用完整路径中的文件名（comm 最多 15 个字符），读不到时依次退回 argv[0] 和 comm
+        let name = exe_path
+            .as_ref()
+            .and_then(|path| file_name(&path.to_string_lossy()))
+            .or_else(|| cmdline.first().and_then(|arg0| file_name(arg0)))
+            .unwrap_or_else(|| stat.comm.clone());
+
+        let start_time = self.start_time_of(&stat);
+
+        Some(ProcessInfo {
+            pid,
+            name,
+            exe_path,
+            cmdline,
+            parent_pid: Some(stat.ppid),
+            start_time,
+            kind,
+            company_name: None,
+            product_name: None,
+        })
+    }
+
+    fn start_time(&self, pid: u32) -> Option<SystemTime> {
+        let stat = parse_stat(&fs::read_to_string(self.root.join(pid.to_string()).join("stat")).ok()?)?;
+        self.start_time_of(&stat)
+    }
+
+    fn tree_node(&self, pid: u32) -> Option<TreeNode> {
+        let stat = parse_stat(&fs::read_to_string(self.root.join(pid.to_string()).join("stat")).ok()?)?;
+        Some(TreeNode { parent_pid: Some(stat.ppid), group_id: Some(stat.pgrp), start_time: self.start_time_of(&stat) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 在临时目录中构造一个假的 /proc
+    struct FakeProc {
+        root: PathBuf,
+    }
+
+    impl FakeProc {
+        fn new(name: &str) -> Self {
+            let root = std::env::temp_dir().join(format!("silencer-proc-{}-{}", std::process::id(), name));
+            let _ = fs::remove_dir_all(&root);
+            fs::create_dir_all(&root).unwrap();
+            fs::write(root.join("stat"), "cpu  1 2 3 4\nbtime 1700000000\nprocesses 42\n").unwrap();
+            Self { root }
+        }
+
+        fn add(&self, pid: u32, stat: &str, cmdline: &[u8], exe: Option<&str>) {
+            let dir = self.root.join(pid.to_string());
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(dir.join("stat"), stat).unwrap();
+            fs::write(dir.join("cmdline"), cmdline).unwrap();
+            if let Some(exe) = exe {
+                std::os::unix::fs::symlink(exe, dir.join("exe")).unwrap();
+            }
+        }
+
+        fn provider(&self) -> ProcProcessInfo {
+            ProcProcessInfo { root: self.root.clone(), clock_ticks: 100 }
+        }
+    }
+
+    impl Drop for FakeProc {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    fn stat_line(pid: u32, comm: &str, state: char, ppid: u32, flags: u64, start_ticks: u64) -> String {
+        format!("{pid} ({comm}) {state} {ppid} {pid} {pid} 0 -1 {flags} 0 0 0 0 0 0 0 0 20 0 1 0 {start_ticks} 1000 10 0")
+    }
+
+    #[test]
+    fn large_tick_counts_do_not_overflow() {
+        assert_eq!(ticks_to_duration(250, 100), Duration::from_millis(2500));
+        assert_eq!(ticks_to_duration(u64::MAX, 100), Duration::new(u64::MAX / 100, 150_000_000));
+    }
+
+    #[test]
+    fn parses_stat_with_spaces_and_parens_in_comm() {
+        let stat = parse_stat(&stat_line(10, "Web Content (x)", 'S', 1, 0, 500)).unwrap();
+
+        assert_eq!(stat, Stat { comm: "Web Content (x)".to_string(), state: 'S', ppid: 1, pgrp: 10, flags: 0, start_ticks: 500 });
+        assert!(parse_stat("10 (truncated").is_none());
+        assert!(parse_stat("").is_none());
+    }
+
+    #[test]
+    fn normal_process_uses_exe_name_over_truncated_comm() {
+        let proc = FakeProc::new("normal");
+        proc.add(
+            100,
+            &stat_line(100, "Game-Win64-Shi", 'S', 1, 0x400000, 250),
+            b"/opt/game/Game-Win64-Shipping\0-windowed\0",
+            Some("/opt/game/Game-Win64-Shipping"),
+        );
+
+        let info = proc.provider().process_info(100).unwrap();
+        assert_eq!(info.name, "Game-Win64-Shipping");
+        assert_eq!(info.exe_path, Some(PathBuf::from("/opt/game/Game-Win64-Shipping")));
+        assert_eq!(info.cmdline, ["/opt/game/Game-Win64-Shipping", "-windowed"]);
+        assert_eq!(info.parent_pid, Some(1));
+        assert_eq!(info.start_time, Some(SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_002_500)));
+        assert_eq!(proc.provider().start_time(100), info.start_time);
+        assert_eq!(info.kind, ProcessKind::Normal);
+    }
+
+    #[test]
+    fn deleted_executable_suffix_is_stripped() {
+        let proc = FakeProc::new("deleted");
+        proc.add(100, &stat_line(100, "player", 'S', 1, 0, 0), b"player\0", Some("/usr/bin/player (deleted)"));
+
+        let info = proc.provider().process_info(100).unwrap();
+        assert_eq!(info.exe_path, Some(PathBuf::from("/usr/bin/player")));
+        assert_eq!(info.name, "player");
+    }
+
+    #[test]
+    fn unreadable_exe_falls_back_to_argv0_then_comm() {
+        let proc = FakeProc::new("noexe");
+        proc.add(100, &stat_line(100, "chrome", 'S', 1, 0, 0), b"/opt/google/chrome/chrome\0--type=renderer\0", None);
+        proc.add(101, &stat_line(101, "daemon", 'S', 1, 0, 0), b"", None);
+
+        let provider = proc.provider();
+        let chrome = provider.process_info(100).unwrap();
+        assert_eq!(chrome.exe_path, None);
+        assert_eq!(chrome.name, "chrome");
+        assert_eq!(provider.process_info(101).unwrap().name, "daemon");
+    }
+
+    #[test]
+    fn zombie_has_no_exe_or_cmdline() {
+        let proc = FakeProc::new("zombie");
+        proc.add(100, &stat_line(100, "defunct-app", 'Z', 55, 0, 0), b"", Some("/usr/bin/defunct-app"));
+
+        let info = proc.provider().process_info(100).unwrap();
+        assert_eq!(info.kind, ProcessKind::Zombie);
+        assert_eq!(info.name, "defunct-app");
+        assert_eq!(info.exe_path, None);
+        assert_eq!(info.parent_pid, Some(55));
+    }
+
+    #[test]
+    fn kernel_thread_is_detected_by_flags() {
+        let proc = FakeProc::new("kthread");
+        proc.add(2, &stat_line(2, "kthreadd", 'S', 0, 0x208040, 0), b"", None);
+
+        let info = proc.provider().process_info(2).unwrap();
+        assert_eq!(info.kind, ProcessKind::KernelThread);
+        assert_eq!(info.name, "kthreadd");
+        assert_eq!(info.parent_pid, Some(0));
+        assert!(info.cmdline.is_empty());
+    }
+
+    #[test]
+    fn tree_node_reads_parent_and_process_group() {
+        let proc = FakeProc::new("tree");
+        proc.add(100, &stat_line(100, "game", 'S', 42, 0, 250), b"game\0", None);
+
+        let node = proc.provider().tree_node(100).unwrap();
+        assert_eq!(node, TreeNode { parent_pid: Some(42), group_id: Some(100), start_time: proc.provider().start_time(100) });
+        assert!(proc.provider().tree_node(101).is_none());
+    }
+
+    #[test]
+    fn missing_process_and_fallback_name() {
+        let proc = FakeProc::new("missing");
+        let provider = proc.provider();
+
+        assert!(provider.process_info(12345).is_none());
+        assert_eq!(provider.process_name(12345), "进程 (12345)");
+        assert_eq!(provider.process_name(0), "System");
+    }
+
+    #[test]
+    fn malformed_stat_still_reports_the_process() {
+        let proc = FakeProc::new("malformed");
+        proc.add(100, "garbage", b"", None);
+
+        let info = proc.provider().process_info(100).unwrap();
+        assert_eq!(info.name, "进程 (100)");
+        assert_eq!(info.parent_pid, None);
+    }
+
+    #[test]
+    fn reads_the_current_process_from_real_proc() {
+        let provider = ProcProcessInfo::new();
+        let info = provider.process_info(std::process::id()).unwrap();
+        let exe = std::env::current_exe().unwrap();
+
+        assert_eq!(info.exe_path.as_deref(), Some(exe.as_path()));
+        assert_eq!(info.name, exe.file_name().unwrap().to_string_lossy());
+        assert_eq!(info.kind, ProcessKind::Normal);
+        assert!(info.start_time.unwrap() <= SystemTime::now());
+        assert!(!info.cmdline.is_empty());
+    }
+}
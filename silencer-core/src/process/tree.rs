@@ -0,0 +1,162 @@
+use std::cell::{OnceCell, RefCell};
+use std::collections::HashMap;
+
+use super::{ProcessInfoProvider, TreeNode};
+
+// 祖先链的最大长度，防止异常数据导致无限循环
+const MAX_DEPTH: usize = 64;
+
+/// 一次同步期间的进程树视图：按需查询进程的父进程与进程组并缓存，用于判断会话进程与前台进程的关系。
+/// 不应跨同步长期保存，进程退出后父子关系会变化
+pub struct ProcessTree<'a> {
+    provider: &'a dyn ProcessInfoProvider,
+    nodes: RefCell<HashMap<u32, Option<TreeNode>>>,
+    // 提供者一次性给出的父进程表与桌面外壳进程，第一次需要时读取
+    parents: OnceCell<Option<HashMap<u32, u32>>>,
+    shell: OnceCell<Option<u32>>,
+}
+
+impl<'a> ProcessTree<'a> {
+    pub fn new(provider: &'a dyn ProcessInfoProvider) -> Self {
+        Self { provider, nodes: RefCell::new(HashMap::new()), parents: OnceCell::new(), shell: OnceCell::new() }
+    }
+
+    fn node(&self, pid: u32) -> Option<TreeNode> {
+        *self.nodes.borrow_mut().entry(pid).or_insert_with(|| match self.parents.get_or_init(|| self.provider.parent_pids()) {
+            Some(parents) => parents.get(&pid).map(|&parent| TreeNode {
+                parent_pid: Some(parent),
+                group_id: None,
+                start_time: self.provider.start_time(pid),
+            }),
+            None => self.provider.tree_node(pid),
+        })
+    }
+
+    fn shell(&self) -> Option<u32> {
+        *self.shell.get_or_init(|| self.provider.shell_pid())
+    }
+
+    /// 进程的祖先链（父进程、祖父进程……）。父进程已退出且 PID 被更晚启动的进程复用时，链在此中断。
+    /// 链也在桌面外壳进程处中断，否则前台为桌面或任务栏时，从它启动的所有程序都会被视为前台的后代
+    pub fn ancestors(&self, pid: u32) -> Vec<u32> {
+        let mut chain = Vec::new();
+        let mut current = pid;
+        let mut node = self.node(pid);
+        while let Some(child) = node
+            && let Some(parent) = child.parent_pid
+            && parent != 0
+            && parent != current
+            && Some(parent) != self.shell()
+            && !chain.contains(&parent)
+            && chain.len() < MAX_DEPTH
+        {
+            let parent_node = self.node(parent);
+            let started_later = matches!(
+                (child.start_time, parent_node.and_then(|node| node.start_time)),
+                (Some(child), Some(parent)) if parent > child
+            );
+            if started_later {
+                break;
+            }
+            chain.push(parent);
+            current = parent;
+            node = parent_node;
+        }
+        chain
+    }
+
+    /// `pid` 是否是 `ancestor` 的后代进程（不含自身）
+    pub fn is_descendant(&self, pid: u32, ancestor: u32) -> bool {
+        pid != ancestor && self.ancestors(pid).contains(&ancestor)
+    }
+
+    /// 两个进程是否属于同一进程组，任一进程组未知时返回 false
+    pub fn same_group(&self, a: u32, b: u32) -> bool {
+        match (self.node(a).and_then(|node| node.group_id), self.node(b).and_then(|node| node.group_id)) {
+            (Some(a), Some(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::fake::FakeProcesses;
+
+    // launcher (1) -> browser (10) -> audio service (11) -> helper (12)
+    fn processes() -> FakeProcesses {
+        FakeProcesses::new()
+            .with(1, "launcher", 0)
+            .with(10, "browser", 1)
+            .with(11, "audio-service", 10)
+            .with(12, "helper", 11)
+            .with(20, "music", 1)
+    }
+
+    #[test]
+    fn walks_up_to_the_root() {
+        let processes = processes();
+        let tree = ProcessTree::new(&processes);
+
+        assert_eq!(tree.ancestors(12), vec![11, 10, 1]);
+        assert!(tree.is_descendant(12, 10));
+        assert!(tree.is_descendant(11, 1));
+        assert!(!tree.is_descendant(10, 10));
+        assert!(!tree.is_descendant(20, 10));
+        assert!(tree.ancestors(99).is_empty());
+    }
+
+    #[test]
+    fn reused_parent_pid_breaks_the_chain() {
+        // 30 的父进程 40 已退出，PID 40 之后被一个无关的新进程复用
+        let processes = FakeProcesses::new().with(30, "orphan", 40).with(40, "newcomer", 0);
+        let tree = ProcessTree::new(&processes);
+
+        assert!(tree.ancestors(30).is_empty());
+    }
+
+    #[test]
+    fn cycles_terminate() {
+        let processes = FakeProcesses::new().with(1, "a", 2).with(2, "b", 1);
+        let tree = ProcessTree::new(&processes);
+
+        assert!(tree.ancestors(1).len() <= 2);
+    }
+
+    #[test]
+    fn shell_is_not_an_ancestor() {
+        // explorer (1) 启动了 launcher (10) 与 music (20)，launcher 又启动了 game (11)
+        let processes = FakeProcesses::new()
+            .with(1, "explorer.exe", 0)
+            .with(10, "launcher.exe", 1)
+            .with(11, "game.exe", 10)
+            .with(20, "music.exe", 1)
+            .with_shell(1);
+        let tree = ProcessTree::new(&processes);
+
+        assert_eq!(tree.ancestors(11), vec![10]);
+        assert!(!tree.is_descendant(20, 1));
+    }
+
+    #[test]
+    fn parent_table_is_read_once() {
+        let processes = processes().with_parent_table();
+        let tree = ProcessTree::new(&processes);
+
+        assert_eq!(tree.ancestors(12), vec![11, 10, 1]);
+        assert!(!tree.is_descendant(20, 10));
+        assert!(tree.ancestors(99).is_empty());
+        assert_eq!(processes.parent_table_reads(), 1);
+    }
+
+    #[test]
+    fn process_groups_are_compared_when_known() {
+        let processes = processes().with_group(10, 10).with_group(11, 10).with_group(20, 20);
+        let tree = ProcessTree::new(&processes);
+
+        assert!(tree.same_group(11, 10));
+        assert!(!tree.same_group(20, 10));
+        assert!(!tree.same_group(12, 12));
+    }
+}
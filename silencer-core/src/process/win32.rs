@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use windows::core::PCWSTR;
+use windows::Win32::Foundation::*;
+use windows::Win32::Storage::FileSystem::*;
+use windows::Win32::System::Diagnostics::ToolHelp::*;
+use windows::Win32::System::Threading::*;
+
+use super::{ProcessInfo, ProcessInfoProvider, ProcessKind, TreeNode};
+use crate::utils;
+
+// 1601-01-01 到 1970-01-01 之间的 100ns 间隔数
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+/// 基于 Win32 API 的进程信息提供者。读取其它进程的命令行需要访问其 PEB，这里不提供
+pub struct Win32ProcessInfo;
+
+/// 遍历一次进程快照，`visit` 返回 false 时停止。无法创建快照时返回 None
+fn walk_snapshot(mut visit: impl FnMut(&PROCESSENTRY32W) -> bool) -> Option<()> {
+    unsafe {
+        let snapshot = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).ok()?;
+        let mut entry = PROCESSENTRY32W { dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32, ..Default::default() };
+        let mut ok = Process32FirstW(snapshot, &mut entry).is_ok();
+        while ok && visit(&entry) {
+            ok = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+        Some(())
+    }
+}
+
+/// 在进程快照中查找指定 PID，返回 (父进程 PID, 可执行文件名)
+fn snapshot_entry(pid: u32) -> Option<(u32, String)> {
+    let mut found = None;
+    walk_snapshot(|entry| {
+        if entry.th32ProcessID != pid {
+            return true;
+        }
+        let len = entry.szExeFile.iter().position(|&c| c == 0).unwrap_or(entry.szExeFile.len());
+        found = Some((entry.th32ParentProcessID, String::from_utf16_lossy(&entry.szExeFile[..len])));
+        false
+    })?;
+    found
+}
+
+fn start_time(pid: u32) -> Option<SystemTime> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut creation = FILETIME::default();
+        let (mut exit, mut kernel, mut user) = (FILETIME::default(), FILETIME::default(), FILETIME::default());
+        let result = GetProcessTimes(handle, &mut creation, &mut exit, &mut kernel, &mut user);
+        let _ = CloseHandle(handle);
+        result.ok()?;
+        let ticks = ((creation.dwHighDateTime as u64) << 32) | creation.dwLowDateTime as u64;
+        Some(SystemTime::UNIX_EPOCH + Duration::from_nanos(ticks.checked_sub(FILETIME_UNIX_EPOCH)? * 100))
+    }
+}
+
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
+/// 读取可执行文件版本信息中的 (CompanyName, ProductName)，使用文件声明的第一种语言
+fn version_strings(path: &str) -> (Option<String>, Option<String>) {
+    unsafe {
+        let path = wide(path);
+        let size = GetFileVersionInfoSizeW(PCWSTR(path.as_ptr()), None);
+        if size == 0 {
+            return (None, None);
+        }
+        let mut data = vec![0u8; size as usize];
+        if GetFileVersionInfoW(PCWSTR(path.as_ptr()), 0, size, data.as_mut_ptr().cast()).is_err() {
+            return (None, None);
+        }
+
+        let query = |sub_block: &str| -> Option<(*const u8, u32)> {
+            let sub_block = wide(sub_block);
+            let mut buffer = std::ptr::null_mut();
+            let mut len = 0u32;
+            VerQueryValueW(data.as_ptr().cast(), PCWSTR(sub_block.as_ptr()), &mut buffer, &mut len)
+                .as_bool()
+                .then_some((buffer as *const u8, len))
+                .filter(|(buffer, len)| !buffer.is_null() && *len > 0)
+        };
+
+        // Translation 是 (语言, 代码页) 的 u16 数组
+        let Some((translation, _)) = query("\\VarFileInfo\\Translation") else {
+            return (None, None);
+        };
+        let translation = translation as *const u16;
+        let (language, codepage) = (*translation, *translation.add(1));
+
+        let string = |name: &str| {
+            let (buffer, len) = query(&format!("\\StringFileInfo\\{:04x}{:04x}\\{}", language, codepage, name))?;
+            // len 为字符数，包含结尾的 0
+            let chars = std::slice::from_raw_parts(buffer as *const u16, len as usize);
+            let value = String::from_utf16_lossy(chars.split(|&c| c == 0).next().unwrap_or_default());
+            let value = value.trim();
+            (!value.is_empty()).then(|| value.to_string())
+        };
+        (string("CompanyName"), string("ProductName"))
+    }
+}
+
+impl ProcessInfoProvider for Win32ProcessInfo {
+    fn process_info(&self, pid: u32) -> Option<ProcessInfo> {
+        let (parent_pid, exe_file) = snapshot_entry(pid)?;
+        let exe_path = utils::get_process_path_by_pid(pid).map(PathBuf::from);
+        let name = exe_path
+            .as_ref()
+            .and_then(|path| path.file_name())
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or(exe_file);
+
+        let (company_name, product_name) =
+            exe_path.as_ref().map(|path| version_strings(&path.to_string_lossy())).unwrap_or_default();
+
+        Some(ProcessInfo {
+            pid,
+            name,
+            exe_path,
+            cmdline: Vec::new(),
+            parent_pid: Some(parent_pid),
+            start_time: start_time(pid),
+            kind: ProcessKind::Normal,
+            company_name,
+            product_name,
+        })
+    }
+
+    fn start_time(&self, pid: u32) -> Option<SystemTime> {
+        start_time(pid)
+    }
+
+    // 不读取版本信息；进程所属的作业对象无法从外部查询，因此没有进程组
+    fn tree_node(&self, pid: u32) -> Option<TreeNode> {
+        let (parent_pid, _) = snapshot_entry(pid)?;
+        Some(TreeNode { parent_pid: Some(parent_pid), group_id: None, start_time: start_time(pid) })
+    }
+
+    // 每次查询父进程都要遍历整个快照，构建进程树时一次读出所有进程
+    fn parent_pids(&self) -> Option<HashMap<u32, u32>> {
+        let mut parents = HashMap::new();
+        walk_snapshot(|entry| {
+            parents.insert(entry.th32ProcessID, entry.th32ParentProcessID);
+            true
+        })?;
+        Some(parents)
+    }
+
+    fn shell_pid(&self) -> Option<u32> {
+        utils::get_shell_pid()
+    }
+}
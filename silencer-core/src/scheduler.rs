@@ -0,0 +1,211 @@
+use std::time::{Duration, Instant};
+
+/// 前台切换事件的防抖间隔，防止极速切屏时的性能抖动
+pub const DEBOUNCE: Duration = Duration::from_millis(50);
+/// 周期性强制同步的间隔，确保即使错过事件也能恢复正确状态
+pub const FORCED_SYNC: Duration = Duration::from_millis(200);
+/// 后端不支持会话事件时，重新枚举会话列表（用于界面展示）的间隔
+pub const SESSION_REFRESH: Duration = Duration::from_secs(2);
+/// 淡入淡出期间调整一次音量的间隔
+pub const FADE_STEP: Duration = Duration::from_millis(20);
+
+/// 一次调度后需要执行的动作
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tick {
+    pub refresh_sessions: bool,
+    pub enforce: bool,
+    /// 推进正在进行的淡入淡出
+    pub step_fades: bool,
+}
+
+/// 防抖 + 周期同步调度器，淡入淡出期间另按 [`FADE_STEP`] 推进音量，宽限期到期时额外同步一次。本身不读取时钟，由调用方传入当前时间
+pub struct Scheduler {
+    last_refresh: Instant,
+    last_enforcement: Instant,
+    poll_sessions: bool,
+    fading: bool,
+    last_fade_step: Instant,
+    grace_deadline: Option<Instant>,
+}
+
+impl Scheduler {
+    pub fn new(now: Instant) -> Self {
+        Self { last_refresh: now, last_enforcement: now, poll_sessions: true, fading: false, last_fade_step: now, grace_deadline: None }
+    }
+
+    /// 有淡入淡出正在进行时开启定时推进，刚开始时从 `now` 起计时
+    pub fn set_fading(&mut self, fading: bool, now: Instant) {
+        if fading && !self.fading {
+            self.last_fade_step = now;
+        }
+        self.fading = fading;
+    }
+
+    /// 调用方在调度之外执行了一次同步，周期同步从 `now` 重新计时
+    pub fn mark_enforced(&mut self, now: Instant) {
+        self.last_enforcement = now;
+    }
+
+    /// 最早结束的宽限期，到期时不等防抖或周期同步立即同步一次
+    pub fn set_grace_deadline(&mut self, deadline: Option<Instant>) {
+        self.grace_deadline = deadline;
+    }
+
+    /// 后端能够推送会话变化时关闭定时刷新会话列表
+    pub fn set_poll_sessions(&mut self, poll_sessions: bool) {
+        self.poll_sessions = poll_sessions;
+    }
+
+    /// 根据当前时间与自上次调度以来是否收到前台切换事件，决定是否刷新会话列表与执行同步
+    pub fn tick(&mut self, now: Instant, event_triggered: bool, running: bool) -> Tick {
+        let mut tick = Tick::default();
+
+        if self.poll_sessions && now.duration_since(self.last_refresh) >= SESSION_REFRESH {
+            tick.refresh_sessions = true;
+            self.last_refresh = now;
+        }
+
+        if self.fading && now.duration_since(self.last_fade_step) >= FADE_STEP {
+            tick.step_fades = true;
+            self.last_fade_step = now;
+        }
+
+        if running {
+            let elapsed = now.duration_since(self.last_enforcement);
+            let grace_expired = self.grace_deadline.is_some_and(|deadline| now >= deadline);
+            if (event_triggered && elapsed >= DEBOUNCE) || elapsed >= FORCED_SYNC || grace_expired {
+                tick.enforce = true;
+                self.last_enforcement = now;
+                // 同步后由调用方设置新的到期时间
+                self.grace_deadline = None;
+            }
+        }
+
+        tick
+    }
+
+    /// 下一次需要调用 [`Scheduler::tick`] 的时间点，供后台线程决定休眠多久；`None` 表示无需定时唤醒
+    pub fn next_deadline(&self, event_pending: bool, running: bool) -> Option<Instant> {
+        let refresh = self.poll_sessions.then(|| self.last_refresh + SESSION_REFRESH);
+        let fade = self.fading.then(|| self.last_fade_step + FADE_STEP);
+        let enforcement = running.then(|| self.last_enforcement + if event_pending { DEBOUNCE } else { FORCED_SYNC });
+        let grace = self.grace_deadline.filter(|_| running);
+        [refresh, fade, enforcement, grace].into_iter().flatten().min()
+    }
+
+    /// 会话列表被手动刷新后调用，推迟下一次定时刷新
+    pub fn mark_refreshed(&mut self, now: Instant) {
+        self.last_refresh = now;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(base: Instant, ms: u64) -> Instant {
+        base + Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn events_are_debounced() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(t0);
+
+        assert!(!scheduler.tick(ms(t0, 30), true, true).enforce);
+        assert!(scheduler.tick(ms(t0, 50), true, true).enforce);
+        assert!(!scheduler.tick(ms(t0, 60), true, true).enforce);
+    }
+
+    #[test]
+    fn forced_sync_without_events() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(t0);
+
+        assert!(!scheduler.tick(ms(t0, 199), false, true).enforce);
+        assert!(scheduler.tick(ms(t0, 200), false, true).enforce);
+        assert!(!scheduler.tick(ms(t0, 300), false, true).enforce);
+        assert!(scheduler.tick(ms(t0, 400), false, true).enforce);
+    }
+
+    #[test]
+    fn stopped_never_enforces_but_still_refreshes() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(t0);
+
+        let tick = scheduler.tick(ms(t0, 2000), true, false);
+        assert_eq!(tick, Tick { refresh_sessions: true, enforce: false, step_fades: false });
+        assert!(!scheduler.tick(ms(t0, 3000), false, false).refresh_sessions);
+        assert!(scheduler.tick(ms(t0, 4000), false, false).refresh_sessions);
+    }
+
+    #[test]
+    fn deadline_follows_pending_events() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(t0);
+
+        assert_eq!(scheduler.next_deadline(false, true), Some(ms(t0, 200)));
+        assert_eq!(scheduler.next_deadline(true, true), Some(ms(t0, 50)));
+        assert_eq!(scheduler.next_deadline(true, false), Some(ms(t0, 2000)));
+
+        scheduler.tick(ms(t0, 200), false, true);
+        assert_eq!(scheduler.next_deadline(true, true), Some(ms(t0, 250)));
+        assert_eq!(scheduler.next_deadline(false, true), Some(ms(t0, 400)));
+    }
+
+    #[test]
+    fn event_driven_sessions_disable_polling() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(t0);
+        scheduler.set_poll_sessions(false);
+
+        assert!(!scheduler.tick(ms(t0, 5000), false, false).refresh_sessions);
+        assert_eq!(scheduler.next_deadline(false, false), None);
+        assert_eq!(scheduler.next_deadline(false, true), Some(ms(t0, 200)));
+    }
+
+    #[test]
+    fn manual_refresh_postpones_the_next_one() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(t0);
+
+        scheduler.mark_refreshed(ms(t0, 1500));
+        assert!(!scheduler.tick(ms(t0, 2000), false, false).refresh_sessions);
+        assert!(scheduler.tick(ms(t0, 3500), false, false).refresh_sessions);
+    }
+
+    #[test]
+    fn fades_step_at_their_own_pace() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(t0);
+        scheduler.set_poll_sessions(false);
+
+        scheduler.set_fading(true, ms(t0, 10));
+        assert_eq!(scheduler.next_deadline(false, true), Some(ms(t0, 30)));
+        assert!(!scheduler.tick(ms(t0, 29), false, true).step_fades);
+        assert!(scheduler.tick(ms(t0, 30), false, true).step_fades);
+        // 已在渐变中时再次开启不会重新计时
+        scheduler.set_fading(true, ms(t0, 45));
+        assert_eq!(scheduler.next_deadline(false, false), Some(ms(t0, 50)));
+
+        scheduler.set_fading(false, ms(t0, 50));
+        assert!(!scheduler.tick(ms(t0, 100), false, true).step_fades);
+        assert_eq!(scheduler.next_deadline(false, false), None);
+    }
+
+    #[test]
+    fn grace_deadline_triggers_an_extra_sync() {
+        let t0 = Instant::now();
+        let mut scheduler = Scheduler::new(t0);
+        scheduler.set_poll_sessions(false);
+
+        scheduler.tick(ms(t0, 200), false, true);
+        scheduler.set_grace_deadline(Some(ms(t0, 330)));
+        assert_eq!(scheduler.next_deadline(false, true), Some(ms(t0, 330)));
+        assert_eq!(scheduler.next_deadline(false, false), None);
+        assert!(!scheduler.tick(ms(t0, 329), false, true).enforce);
+        assert!(scheduler.tick(ms(t0, 330), false, true).enforce);
+        // 到期后的同步从头计算周期同步
+        assert_eq!(scheduler.next_deadline(false, true), Some(ms(t0, 530)));
+    }
+}
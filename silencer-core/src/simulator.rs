@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+
+use crate::audio::fake::FakeBackend;
+use crate::audio::{AudioManager, ExternalChange};
+use crate::clock::VirtualClock;
+use crate::config::Config;
+use crate::engine::Engine;
+use crate::foreground::ForegroundProvider;
+
+/// 脚本中的一个事件
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// 前台切换到该 PID，并发出切换通知
+    Focus(u32),
+    /// 前台切换到该 PID，但通知丢失（模拟钩子漏掉事件）
+    FocusWithoutEvent(u32),
+    /// 新的音频会话出现，后端发出会话通知
+    SessionStart { name: String, pid: u32 },
+    /// 新的音频会话出现，但后端没有发出通知（模拟不支持会话事件的后端）
+    SessionStartWithoutEvent { name: String, pid: u32 },
+    /// 该 PID 的所有会话消失
+    SessionEnd(u32),
+    /// 用户在系统音量合成器中修改了该 PID 的静音状态，后端发出外部修改通知
+    ExternalMute { pid: u32, muted: bool },
+    /// 该 PID 的主窗口标题变化，并发出与前台切换相同的通知
+    TitleChange { pid: u32, title: String },
+    /// 点击开始/停止运行
+    SetRunning(bool),
+    /// 音频后端恢复或变得不可用（例如音频服务重启），不可用期间所有调用都失败
+    BackendAvailable(bool),
+}
+
+/// 时间线上的一次静音状态变化（无论由引擎还是外部造成）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MuteChange {
+    pub at_ms: u64,
+    pub pid: u32,
+    pub muted: bool,
+}
+
+struct ScriptedForeground(Arc<AtomicU32>);
+
+impl ForegroundProvider for ScriptedForeground {
+    fn foreground_pid(&self) -> u32 {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// 在虚拟时钟上按毫秒回放事件脚本，驱动真实的 [`Engine`] 与 [`FakeBackend`]，
+/// 记录每个会话静音状态的精确变化时间，用于回归测试防抖与周期同步逻辑
+pub struct Simulator {
+    clock: VirtualClock,
+    backend: Arc<FakeBackend>,
+    foreground: Arc<AtomicU32>,
+    engine: Engine,
+    // 后端发出、尚未交给引擎的外部修改通知
+    changes: Arc<Mutex<Vec<ExternalChange>>>,
+    script: Vec<(u64, Event)>,
+}
+
+impl Simulator {
+    /// 以给定配置创建模拟器，初始没有任何会话，前台 PID 为 0
+    pub fn new(config: Config) -> Self {
+        let clock = VirtualClock::new();
+        let backend = Arc::new(FakeBackend::new());
+        let foreground = Arc::new(AtomicU32::new(0));
+        let audio = AudioManager::with_backend(Box::new(Arc::clone(&backend)));
+        let mut engine = Engine::with_parts(config, audio, Box::new(ScriptedForeground(Arc::clone(&foreground))))
+            .with_clock(clock.clone());
+        let changes: Arc<Mutex<Vec<ExternalChange>>> = Default::default();
+        let queue = Arc::clone(&changes);
+        engine.watch_external_changes(Arc::new(move |change| queue.lock().unwrap().push(change)));
+        Self { clock, backend, foreground, engine, changes, script: Vec::new() }
+    }
+
+    /// 在第 `at_ms` 毫秒安排一个事件，同一时刻的事件按添加顺序执行
+    pub fn at(mut self, at_ms: u64, event: Event) -> Self {
+        self.script.push((at_ms, event));
+        self
+    }
+
+    /// 从第 0 毫秒运行到第 `until_ms` 毫秒（含），返回静音状态变化的时间线
+    pub fn run(self, until_ms: u64) -> Vec<MuteChange> {
+        self.run_with_syncs(until_ms).0
+    }
+
+    /// 同 [`Simulator::run`]，另外返回引擎执行同步的各个时间点（毫秒）
+    pub fn run_with_syncs(mut self, until_ms: u64) -> (Vec<MuteChange>, Vec<u64>) {
+        self.script.sort_by_key(|(at, _)| *at);
+        let mut script = std::mem::take(&mut self.script).into_iter().peekable();
+        let mut states: HashMap<String, bool> = HashMap::new();
+        let mut timeline = Vec::new();
+        let mut syncs = Vec::new();
+
+        for now_ms in 0..=until_ms {
+            while let Some((_, event)) = script.next_if(|(at, _)| *at == now_ms) {
+                self.apply(event);
+            }
+            let changes = std::mem::take(&mut *self.changes.lock().unwrap());
+            for change in changes {
+                self.engine.external_change(change);
+            }
+            if self.engine.tick().enforce {
+                syncs.push(now_ms);
+            }
+
+            for session in self.backend.all_sessions() {
+                let muted = self.backend.state(&session.id).muted;
+                let previous = states.insert(session.id, muted).unwrap_or(false);
+                if previous != muted {
+                    timeline.push(MuteChange { at_ms: now_ms, pid: session.pid, muted });
+                }
+            }
+
+            self.clock.advance(Duration::from_millis(1));
+        }
+        (timeline, syncs)
+    }
+
+    fn apply(&mut self, event: Event) {
+        match event {
+            Event::Focus(pid) => {
+                self.foreground.store(pid, Ordering::SeqCst);
+                self.engine.focus_changed();
+            }
+            Event::FocusWithoutEvent(pid) => self.foreground.store(pid, Ordering::SeqCst),
+            Event::SessionStart { name, pid } => {
+                self.backend.add_session(&name, pid);
+                self.engine.sessions_changed();
+            }
+            Event::SessionStartWithoutEvent { name, pid } => {
+                self.backend.add_session(&name, pid);
+            }
+            Event::SessionEnd(pid) => {
+                for session in self.backend.all_sessions() {
+                    if session.pid == pid {
+                        self.backend.remove_session(&session.id);
+                    }
+                }
+                self.engine.sessions_changed();
+            }
+            Event::ExternalMute { pid, muted } => {
+                for session in self.backend.all_sessions() {
+                    if session.pid == pid {
+                        self.backend.user_set_mute(&session.id, muted);
+                    }
+                }
+            }
+            Event::TitleChange { pid, title } => {
+                for session in self.backend.all_sessions() {
+                    if session.pid == pid {
+                        self.backend.set_window_title(&session.id, &title);
+                    }
+                }
+                self.engine.focus_changed();
+            }
+            Event::SetRunning(running) => self.engine.set_running(running),
+            Event::BackendAvailable(available) => self.backend.set_unavailable(!available),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ExternalChangePolicy;
+
+    const GAME: u32 = 10;
+    const BROWSER: u32 = 20;
+    const OTHER: u32 = 30;
+
+    fn change(at_ms: u64, pid: u32, muted: bool) -> MuteChange {
+        MuteChange { at_ms, pid, muted }
+    }
+
+    /// 黑名单模式下只管理 game.exe，游戏与浏览器均已在播放，游戏位于前台并已开始运行
+    fn game_in_blacklist() -> Simulator {
+        game_in_blacklist_with(ExternalChangePolicy::Override)
+    }
+
+    fn game_in_blacklist_with(external_change_policy: ExternalChangePolicy) -> Simulator {
+        let mut config = Config { external_change_policy, ..Default::default() };
+        config.list.insert("game.exe".to_owned());
+        Simulator::new(config)
+            .at(0, Event::SessionStart { name: "game.exe".to_owned(), pid: GAME })
+            .at(0, Event::SessionStart { name: "browser.exe".to_owned(), pid: BROWSER })
+            .at(0, Event::Focus(GAME))
+            .at(0, Event::SetRunning(true))
+    }
+
+    #[test]
+    fn switching_away_mutes_immediately_after_a_quiet_period() {
+        let timeline = game_in_blacklist().at(100, Event::Focus(BROWSER)).run(150);
+        assert_eq!(timeline, vec![change(100, GAME, true)]);
+    }
+
+    /// 同 [`game_in_blacklist`]，后台淡出用时 100 毫秒
+    fn game_fading_out() -> Simulator {
+        let mut config = Config { fade_out: crate::config::Fade { duration_ms: 100, ..Default::default() }, ..Default::default() };
+        config.list.insert("game.exe".to_owned());
+        Simulator::new(config)
+            .at(0, Event::SessionStart { name: "game.exe".to_owned(), pid: GAME })
+            .at(0, Event::Focus(GAME))
+            .at(0, Event::SetRunning(true))
+    }
+
+    #[test]
+    fn fade_out_mutes_once_the_ramp_finishes() {
+        let timeline = game_fading_out().at(100, Event::Focus(BROWSER)).run(300);
+        assert_eq!(timeline, vec![change(200, GAME, true)]);
+    }
+
+    #[test]
+    fn changes_during_a_fade_out_are_not_adopted_as_rules() {
+        let mut config = Config {
+            fade_out: crate::config::Fade { duration_ms: 100, ..Default::default() },
+            external_change_policy: ExternalChangePolicy::AdoptAsRule,
+            ..Default::default()
+        };
+        config.list.insert("game.exe".to_owned());
+        // 淡出期间会话保持未静音，后端对渐变本身发出的通知不会被记为新规则
+        let timeline = Simulator::new(config)
+            .at(0, Event::SessionStart { name: "game.exe".to_owned(), pid: GAME })
+            .at(0, Event::Focus(GAME))
+            .at(0, Event::SetRunning(true))
+            .at(100, Event::Focus(BROWSER))
+            .at(150, Event::ExternalMute { pid: GAME, muted: false })
+            .run(400);
+        assert_eq!(timeline, vec![change(200, GAME, true)]);
+    }
+
+    #[test]
+    fn returning_during_a_fade_out_never_mutes() {
+        let timeline = game_fading_out().at(100, Event::Focus(BROWSER)).at(150, Event::Focus(GAME)).run(400);
+        assert_eq!(timeline, vec![]);
+    }
+
+    /// 同 [`game_in_blacklist`]，游戏失去前台 1 秒后才静音
+    fn game_with_grace_period() -> Simulator {
+        let mut config = Config::default();
+        config.list.insert("game.exe".to_owned());
+        config.set_options("game.exe", crate::config::RuleOptions { mute_delay_secs: 1, ..Default::default() });
+        Simulator::new(config)
+            .at(0, Event::SessionStart { name: "game.exe".to_owned(), pid: GAME })
+            .at(0, Event::Focus(GAME))
+            .at(0, Event::SetRunning(true))
+    }
+
+    #[test]
+    fn grace_period_delays_the_mute_past_forced_syncs() {
+        let timeline = game_with_grace_period().at(100, Event::Focus(BROWSER)).run(1500);
+        assert_eq!(timeline, vec![change(1100, GAME, true)]);
+    }
+
+    #[test]
+    fn grace_period_expiring_while_the_backend_fails_does_not_spin() {
+        let (timeline, syncs) = game_with_grace_period()
+            .at(100, Event::Focus(BROWSER))
+            .at(1000, Event::BackendAvailable(false))
+            .at(1500, Event::BackendAvailable(true))
+            .run_with_syncs(2000);
+        // 宽限期在 1100ms 到期时无法同步，之后按周期同步重试，不在已过去的到期时间上反复同步
+        let failed: Vec<u64> = syncs.into_iter().filter(|at| (1000..1500).contains(at)).collect();
+        assert!(failed.len() <= 4, "syncs while the backend failed: {failed:?}");
+        assert_eq!(timeline, vec![change(1500, GAME, true)]);
+    }
+
+    #[test]
+    fn returning_within_the_grace_period_cancels_the_mute() {
+        let timeline = game_with_grace_period()
+            .at(100, Event::Focus(BROWSER))
+            .at(600, Event::Focus(GAME))
+            .at(800, Event::Focus(BROWSER))
+            .run(2000);
+        // 再次离开时重新计时
+        assert_eq!(timeline, vec![change(1800, GAME, true)]);
+    }
+
+    #[test]
+    fn session_appearing_in_the_background_gets_no_grace_period() {
+        let timeline = game_with_grace_period()
+            .at(100, Event::Focus(BROWSER))
+            .at(300, Event::SessionStart { name: "game.exe".to_owned(), pid: OTHER })
+            .run(400);
+        assert_eq!(timeline, vec![change(300, OTHER, true)]);
+    }
+
+    #[test]
+    fn rapid_switching_settles_on_the_last_focus() {
+        // 在防抖间隔内来回切换：不会出现短暂的错误取消静音
+        let timeline = game_in_blacklist()
+            .at(100, Event::Focus(BROWSER))
+            .at(110, Event::Focus(GAME))
+            .at(120, Event::Focus(BROWSER))
+            .run(300);
+        assert_eq!(timeline, vec![change(100, GAME, true)]);
+    }
+
+    #[test]
+    fn returning_within_the_debounce_window_unmutes_once_it_expires() {
+        let timeline = game_in_blacklist()
+            .at(100, Event::Focus(BROWSER))
+            .at(110, Event::Focus(GAME))
+            .run(300);
+        assert_eq!(timeline, vec![change(100, GAME, true), change(150, GAME, false)]);
+    }
+
+    #[test]
+    fn lost_focus_event_is_corrected_by_the_forced_sync() {
+        // 钩子漏掉了切换事件，最迟在下一次 200ms 周期同步时纠正（开始运行后的同步点为 50、250、450ms……）
+        let timeline = game_in_blacklist().at(260, Event::FocusWithoutEvent(BROWSER)).run(500);
+        assert_eq!(timeline, vec![change(450, GAME, true)]);
+    }
+
+    #[test]
+    fn new_background_session_is_muted_as_soon_as_it_appears() {
+        let mut config = Config::default();
+        config.list.insert("game.exe".to_owned());
+        let timeline = Simulator::new(config)
+            .at(0, Event::Focus(BROWSER))
+            .at(0, Event::SetRunning(true))
+            .at(310, Event::SessionStart { name: "game.exe".to_owned(), pid: GAME })
+            .run(500);
+        assert_eq!(timeline, vec![change(310, GAME, true)]);
+    }
+
+    #[test]
+    fn unannounced_session_is_muted_by_the_next_sync() {
+        let mut config = Config::default();
+        config.list.insert("game.exe".to_owned());
+        let timeline = Simulator::new(config)
+            .at(0, Event::Focus(BROWSER))
+            .at(0, Event::SetRunning(true))
+            .at(310, Event::SessionStartWithoutEvent { name: "game.exe".to_owned(), pid: GAME })
+            .run(500);
+        assert_eq!(timeline, vec![change(450, GAME, true)]);
+    }
+
+    #[test]
+    fn external_unmute_is_overridden_by_the_next_sync() {
+        let timeline = game_in_blacklist()
+            .at(100, Event::Focus(BROWSER))
+            .at(450, Event::ExternalMute { pid: GAME, muted: false })
+            .run(700);
+        assert_eq!(timeline, vec![change(100, GAME, true), change(450, GAME, false), change(500, GAME, true)]);
+    }
+
+    #[test]
+    fn external_change_on_an_unknown_session_triggers_a_sync() {
+        let mut config = Config::default();
+        config.list.insert("game.exe".to_owned());
+        // 会话出现时没有通知，引擎缓存的会话列表中还没有它；外部修改通知使引擎立即重新枚举
+        let timeline = Simulator::new(config)
+            .at(0, Event::Focus(BROWSER))
+            .at(0, Event::SetRunning(true))
+            .at(310, Event::SessionStartWithoutEvent { name: "game.exe".to_owned(), pid: GAME })
+            .at(320, Event::ExternalMute { pid: GAME, muted: false })
+            .run(500);
+        assert_eq!(timeline, vec![change(320, GAME, true)]);
+    }
+
+    #[test]
+    fn external_unmute_pauses_the_rule_until_focus_changes() {
+        let timeline = game_in_blacklist_with(ExternalChangePolicy::PauseUntilFocusChange)
+            .at(100, Event::Focus(BROWSER))
+            .at(450, Event::ExternalMute { pid: GAME, muted: false })
+            .at(900, Event::Focus(OTHER))
+            .run(1100);
+        assert_eq!(timeline, vec![change(100, GAME, true), change(450, GAME, false), change(900, GAME, true)]);
+    }
+
+    #[test]
+    fn external_unmute_can_be_adopted_as_a_rule() {
+        let timeline = game_in_blacklist_with(ExternalChangePolicy::AdoptAsRule)
+            .at(100, Event::Focus(BROWSER))
+            .at(450, Event::ExternalMute { pid: GAME, muted: false })
+            .at(900, Event::Focus(OTHER))
+            .run(1100);
+        assert_eq!(timeline, vec![change(100, GAME, true), change(450, GAME, false)]);
+    }
+
+    #[test]
+    fn stopping_unmutes_and_stops_enforcing() {
+        let timeline = game_in_blacklist()
+            .at(100, Event::Focus(BROWSER))
+            .at(250, Event::SetRunning(false))
+            .at(300, Event::Focus(GAME))
+            .at(400, Event::Focus(BROWSER))
+            .run(1000);
+        assert_eq!(timeline, vec![change(100, GAME, true), change(250, GAME, false)]);
+    }
+
+    #[test]
+    fn stopping_keeps_sessions_the_user_had_muted() {
+        let timeline = game_in_blacklist()
+            .at(50, Event::ExternalMute { pid: GAME, muted: true })
+            .at(100, Event::Focus(BROWSER))
+            .at(250, Event::SetRunning(false))
+            .run(400);
+        assert_eq!(timeline, vec![change(50, GAME, true)]);
+    }
+
+    #[test]
+    fn whitelisted_app_stays_audible_in_the_background() {
+        let mut config = Config { is_whitelist: true, ..Default::default() };
+        config.list.insert("game.exe".to_owned());
+        let timeline = Simulator::new(config)
+            .at(0, Event::SessionStart { name: "game.exe".to_owned(), pid: GAME })
+            .at(0, Event::SessionStart { name: "browser.exe".to_owned(), pid: BROWSER })
+            .at(0, Event::Focus(GAME))
+            .at(0, Event::SetRunning(true))
+            .at(100, Event::Focus(BROWSER))
+            .at(300, Event::SessionEnd(GAME))
+            .run(500);
+        assert_eq!(timeline, vec![change(0, BROWSER, true), change(100, BROWSER, false)]);
+    }
+
+    #[test]
+    fn title_rule_follows_the_window_title() {
+        let mut config = Config::default();
+        config.list.insert("title:YouTube".to_owned());
+        let timeline = Simulator::new(config)
+            .at(0, Event::SessionStart { name: "browser.exe".to_owned(), pid: BROWSER })
+            .at(0, Event::TitleChange { pid: BROWSER, title: "Report - Google Docs".to_owned() })
+            .at(0, Event::Focus(GAME))
+            .at(0, Event::SetRunning(true))
+            .at(200, Event::TitleChange { pid: BROWSER, title: "Lofi beats - YouTube".to_owned() })
+            .at(400, Event::TitleChange { pid: BROWSER, title: "Report - Google Docs".to_owned() })
+            .run(600);
+        assert_eq!(timeline, vec![change(200, BROWSER, true), change(400, BROWSER, false)]);
+    }
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use windows::core::*;
+use windows::Win32::Foundation::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+use windows::Win32::System::Threading::*;
+use windows::Win32::System::ProcessStatus::*;
+
+pub fn get_foreground_pid() -> u32 {
+    unsafe {
+        let hwnd = GetForegroundWindow();
+        if hwnd.is_invalid() {
+            return 0;
+        }
+        let mut pid = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        pid
+    }
+}
+
+/// 桌面外壳（任务栏与桌面所属的 explorer.exe）的 PID，外壳未运行时返回 None
+pub fn get_shell_pid() -> Option<u32> {
+    unsafe {
+        let hwnd = GetShellWindow();
+        if hwnd.is_invalid() {
+            return None;
+        }
+        let mut pid = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid));
+        (pid != 0).then_some(pid)
+    }
+}
+
+/// 枚举可见的顶层主窗口（没有所有者、不是工具窗口且标题非空），返回每个进程 Z 序最靠前（最近激活）的主窗口标题
+pub fn get_window_titles() -> HashMap<u32, String> {
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        unsafe {
+            let titles = &mut *(lparam.0 as *mut HashMap<u32, String>);
+            if !IsWindowVisible(hwnd).as_bool()
+                || GetWindow(hwnd, GW_OWNER).is_ok_and(|owner| !owner.is_invalid())
+                || GetWindowLongW(hwnd, GWL_EXSTYLE) as u32 & WS_EX_TOOLWINDOW.0 != 0
+            {
+                return TRUE;
+            }
+            let len = GetWindowTextLengthW(hwnd);
+            if len <= 0 {
+                return TRUE;
+            }
+            let mut buffer = vec![0u16; len as usize + 1];
+            let copied = GetWindowTextW(hwnd, &mut buffer);
+            if copied <= 0 {
+                return TRUE;
+            }
+            let mut pid = 0;
+            GetWindowThreadProcessId(hwnd, Some(&mut pid));
+            titles.entry(pid).or_insert_with(|| String::from_utf16_lossy(&buffer[..copied as usize]));
+            TRUE
+        }
+    }
+
+    let mut titles = HashMap::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut titles as *mut HashMap<u32, String> as isize));
+    }
+    titles
+}
+
+/// 通过 QueryFullProcessImageNameW 获取进程的完整路径，失败时返回 None
+pub fn get_process_path_by_pid(pid: u32) -> Option<String> {
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid).ok()?;
+        let mut buffer = [0u16; 1024];
+        let mut size = buffer.len() as u32;
+        let result = QueryFullProcessImageNameW(handle, PROCESS_NAME_FORMAT(0), PWSTR(buffer.as_mut_ptr()), &mut size);
+        let _ = CloseHandle(handle);
+        result.ok()?;
+        Some(String::from_utf16_lossy(&buffer[..size as usize]))
+    }
+}
+
+pub fn get_process_name_by_pid(pid: u32) -> String {
+    if pid == 0 {
+        return "System".to_string();
+    }
+    
+    // 尝试多种权限打开进程，优先使用受限权限以提高成功率
+    let access_flags = [
+        PROCESS_QUERY_LIMITED_INFORMATION,
+        PROCESS_QUERY_INFORMATION | PROCESS_VM_READ,
+    ];
+
+    for &flags in &access_flags {
+        unsafe {
+            if let Ok(handle) = OpenProcess(flags, false, pid) {
+                let mut buffer = [0u16; 1024];
+                let mut size = buffer.len() as u32;
+                
+                // 优先使用 QueryFullProcessImageNameW，它更现代且支持受限权限
+                if QueryFullProcessImageNameW(handle, PROCESS_NAME_FORMAT(0), PWSTR(buffer.as_mut_ptr()), &mut size).is_ok() {
+                    let path = String::from_utf16_lossy(&buffer[..size as usize]);
+                    let _ = CloseHandle(handle);
+                    if let Some(name) = path.split('\\').last() {
+                        if !name.is_empty() {
+                            return name.to_string();
+                        }
+                    }
+                }
+                
+                // 备选方案：GetModuleBaseNameW
+                let len = GetModuleBaseNameW(handle, None, &mut buffer);
+                let _ = CloseHandle(handle);
+                if len > 0 {
+                    let name = String::from_utf16_lossy(&buffer[..len as usize]);
+                    if !name.is_empty() {
+                        return name.to_string();
+                    }
+                }
+            }
+        }
+    }
+    
+    format!("进程 ({})", pid)
+}
@@ -0,0 +1,365 @@
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::audio::{self, AudioSessionInfo, ChangeNotifier, ExternalChange, SessionNotifier};
+use crate::config::Config;
+use crate::engine::{ConfigChange, Engine};
+use crate::foreground::FocusNotifier;
+use crate::journal::Repair;
+
+/// 前端发给后台线程的命令
+pub enum Command {
+    SetRunning(bool),
+    /// 替换引擎的配置，`revision` 为配置中已合并的引擎修改的版本号（见 [`Snapshot::config_changes`]）
+    SetConfig { config: Config, revision: u64 },
+    RefreshSessions,
+}
+
+/// 后台线程发布的状态快照
+#[derive(Clone, Debug, Default)]
+pub struct Snapshot {
+    pub running: bool,
+    pub sessions: Vec<AudioSessionInfo>,
+    /// 启动时根据日志修复的会话（上次异常退出后遗留的静音）
+    pub repairs: Vec<Repair>,
+    /// 引擎当前运行的配置。前端不应直接用它替换自己的配置，否则会丢掉引擎还没收到的修改
+    pub config: Config,
+    /// 引擎自己对配置的修改中前端尚未确认的部分（见 [`Engine::config_changes`]），前端合并后以 [`Command::SetConfig`] 发回
+    pub config_changes: Vec<(u64, ConfigChange)>,
+}
+
+enum Message {
+    Command(Command),
+    FocusChanged,
+    SessionsChanged,
+    ExternalChange(ExternalChange),
+    Shutdown,
+}
+
+/// 在独立线程上运行 [`Engine`]：该线程持有音频后端（Windows 下包括其 COM 套间），
+/// 自行按防抖/周期同步规则休眠，并在前台切换或会话变化时被唤醒，不依赖界面刷新。前端通过通道发送 [`Command`] 并接收 [`Snapshot`]
+pub struct Worker {
+    messages: Sender<Message>,
+    snapshots: Receiver<Snapshot>,
+    snapshot: Snapshot,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Worker {
+    /// 使用当前平台的后端启动后台线程，每发布一次快照调用一次 `on_update`（例如请求界面重绘）
+    pub fn spawn(config: Config, on_update: Arc<dyn Fn() + Send + Sync>) -> audio::Result<Self> {
+        Self::spawn_with(move |notify| Engine::new(config, notify), on_update)
+    }
+
+    /// 启动后台线程，并在该线程上调用 `make_engine` 创建引擎。
+    /// 传给 `make_engine` 的通知函数应交给前台窗口提供者，用于唤醒后台线程
+    pub fn spawn_with<F>(make_engine: F, on_update: Arc<dyn Fn() + Send + Sync>) -> audio::Result<Self>
+    where
+        F: FnOnce(FocusNotifier) -> audio::Result<Engine> + Send + 'static,
+    {
+        let (message_tx, message_rx) = mpsc::channel();
+        let (snapshot_tx, snapshot_rx) = mpsc::channel();
+        let (ready_tx, ready_rx) = mpsc::sync_channel(1);
+
+        let focus_tx = message_tx.clone();
+        let notify: FocusNotifier = Arc::new(move || {
+            let _ = focus_tx.send(Message::FocusChanged);
+        });
+        let sessions_tx = message_tx.clone();
+        let sessions_notify: SessionNotifier = Arc::new(move || {
+            let _ = sessions_tx.send(Message::SessionsChanged);
+        });
+        let changes_tx = message_tx.clone();
+        let changes_notify: ChangeNotifier = Arc::new(move |change| {
+            let _ = changes_tx.send(Message::ExternalChange(change));
+        });
+
+        let thread = thread::spawn(move || {
+            let mut engine = match make_engine(notify) {
+                Ok(engine) => engine,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e));
+                    return;
+                }
+            };
+            engine.watch_sessions(sessions_notify);
+            engine.watch_external_changes(changes_notify);
+            let _ = ready_tx.send(Ok(snapshot_of(&engine)));
+            run(&mut engine, &message_rx, &snapshot_tx, &*on_update);
+            // 引擎在此线程上析构，恢复会话状态时仍处于同一个 COM 套间
+        });
+
+        let snapshot = match ready_rx.recv() {
+            Ok(Ok(snapshot)) => snapshot,
+            Ok(Err(e)) => {
+                let _ = thread.join();
+                return Err(e);
+            }
+            Err(_) => panic!("worker thread panicked during initialization"),
+        };
+
+        Ok(Self { messages: message_tx, snapshots: snapshot_rx, snapshot, thread: Some(thread) })
+    }
+
+    pub fn send(&self, command: Command) {
+        let _ = self.messages.send(Message::Command(command));
+    }
+
+    /// 取出后台线程发布的最新快照
+    pub fn snapshot(&mut self) -> &Snapshot {
+        while let Ok(snapshot) = self.snapshots.try_recv() {
+            self.snapshot = snapshot;
+        }
+        &self.snapshot
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        let _ = self.messages.send(Message::Shutdown);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+fn snapshot_of(engine: &Engine) -> Snapshot {
+    Snapshot {
+        running: engine.is_running(),
+        sessions: engine.sessions().to_vec(),
+        repairs: engine.repairs().to_vec(),
+        config: engine.config().clone(),
+        config_changes: engine.config_changes().to_vec(),
+    }
+}
+
+fn run(engine: &mut Engine, messages: &Receiver<Message>, snapshots: &Sender<Snapshot>, on_update: &dyn Fn()) {
+    loop {
+        let message = match engine.next_deadline() {
+            Some(deadline) => messages.recv_timeout(deadline.saturating_duration_since(engine.now())),
+            None => messages.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        // 引擎自己修改配置（外部修改记为规则、绑定实例条目）时同样需要发出快照
+        let revision = engine.config_revision();
+        let mut changed = false;
+        match message {
+            Ok(Message::FocusChanged) => engine.focus_changed(),
+            Ok(Message::SessionsChanged) => engine.sessions_changed(),
+            Ok(Message::ExternalChange(change)) => engine.external_change(change),
+            Ok(Message::Command(command)) => {
+                match command {
+                    Command::SetRunning(running) => engine.set_running(running),
+                    Command::SetConfig { config, revision } => engine.set_config(config, revision),
+                    Command::RefreshSessions => engine.refresh_sessions(),
+                }
+                changed = true;
+            }
+            Ok(Message::Shutdown) | Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => {}
+        }
+
+        if engine.tick().refresh_sessions || changed || engine.config_revision() != revision {
+            if snapshots.send(snapshot_of(engine)).is_err() {
+                break;
+            }
+            on_update();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioManager;
+    use crate::audio::fake::FakeBackend;
+    use crate::config::ExternalChangePolicy;
+    use crate::foreground::ForegroundProvider;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::{Duration, Instant};
+
+    struct TestProvider(Arc<AtomicU32>);
+
+    impl ForegroundProvider for TestProvider {
+        fn foreground_pid(&self) -> u32 {
+            self.0.load(Ordering::SeqCst)
+        }
+    }
+
+    fn wait_for(mut condition: impl FnMut() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if condition() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn focus_changes_are_enforced_without_a_frontend_loop() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 10);
+        backend.add_session("music.exe", 20);
+        let foreground = Arc::new(AtomicU32::new(10));
+        let notify_slot: Arc<std::sync::Mutex<Option<FocusNotifier>>> = Default::default();
+
+        let mut config = Config::default();
+        config.list.insert("game.exe".to_owned());
+
+        let mut worker = {
+            let backend = Arc::clone(&backend);
+            let foreground = Arc::clone(&foreground);
+            let notify_slot = Arc::clone(&notify_slot);
+            Worker::spawn_with(
+                move |notify| {
+                    *notify_slot.lock().unwrap() = Some(notify);
+                    let audio = AudioManager::with_backend(Box::new(backend));
+                    Ok(Engine::with_parts(config, audio, Box::new(TestProvider(foreground))))
+                },
+                Arc::new(|| {}),
+            )
+            .unwrap()
+        };
+        assert_eq!(worker.snapshot().sessions.len(), 2);
+
+        worker.send(Command::SetRunning(true));
+        assert!(wait_for(|| worker.snapshot().running));
+        assert!(!backend.state(&game).muted);
+
+        foreground.store(20, Ordering::SeqCst);
+        (notify_slot.lock().unwrap().as_ref().unwrap())();
+        assert!(wait_for(|| backend.state(&game).muted));
+
+        // 退出时恢复原状态
+        drop(worker);
+        assert!(!backend.state(&game).muted);
+    }
+
+    #[test]
+    fn new_sessions_are_published_and_muted_without_polling() {
+        let backend = Arc::new(FakeBackend::new());
+        let foreground = Arc::new(AtomicU32::new(20));
+        let updates = Arc::new(AtomicU32::new(0));
+
+        let mut config = Config::default();
+        config.list.insert("game.exe".to_owned());
+
+        let mut worker = {
+            let backend = Arc::clone(&backend);
+            let foreground = Arc::clone(&foreground);
+            let updates = Arc::clone(&updates);
+            Worker::spawn_with(
+                move |_| {
+                    let audio = AudioManager::with_backend(Box::new(backend));
+                    Ok(Engine::with_parts(config, audio, Box::new(TestProvider(foreground))))
+                },
+                Arc::new(move || {
+                    updates.fetch_add(1, Ordering::SeqCst);
+                }),
+            )
+            .unwrap()
+        };
+        worker.send(Command::SetRunning(true));
+        assert!(wait_for(|| worker.snapshot().running));
+
+        let game = backend.add_session("game.exe", 10);
+        assert!(wait_for(|| worker.snapshot().sessions.len() == 1));
+        assert!(backend.state(&game).muted);
+
+        backend.remove_session(&game);
+        assert!(wait_for(|| worker.snapshot().sessions.is_empty()));
+        assert!(updates.load(Ordering::SeqCst) >= 3);
+    }
+
+    #[test]
+    fn adopted_rules_survive_a_stale_config() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 10);
+        let foreground = Arc::new(AtomicU32::new(20));
+
+        let mut config = Config { external_change_policy: ExternalChangePolicy::AdoptAsRule, ..Default::default() };
+        config.list.insert("game.exe".to_owned());
+
+        let mut worker = {
+            let backend = Arc::clone(&backend);
+            let foreground = Arc::clone(&foreground);
+            let config = config.clone();
+            Worker::spawn_with(
+                move |_| {
+                    let audio = AudioManager::with_backend(Box::new(backend));
+                    Ok(Engine::with_parts(config, audio, Box::new(TestProvider(foreground))))
+                },
+                Arc::new(|| {}),
+            )
+            .unwrap()
+        };
+        worker.send(Command::SetRunning(true));
+        assert!(wait_for(|| backend.state(&game).muted));
+
+        // 用户取消静音被记为规则，同时界面发出一份还没合并该规则的配置
+        backend.user_set_mute(&game, false);
+        let mut edited = config.clone();
+        edited.list.insert("chat.exe".to_owned());
+        worker.send(Command::SetConfig { config: edited.clone(), revision: 0 });
+        assert!(wait_for(|| worker.snapshot().config.list.contains("chat.exe")));
+        let snapshot = worker.snapshot().clone();
+        assert!(!snapshot.config.list.contains("game.exe"));
+        assert!(!backend.state(&game).muted);
+
+        // 界面合并引擎的修改后发回，引擎不再保留已确认的修改
+        assert_eq!(snapshot.config_changes.len(), 1);
+        let (revision, change) = &snapshot.config_changes[0];
+        change.apply(&mut edited);
+        worker.send(Command::SetConfig { config: edited.clone(), revision: *revision });
+        assert!(wait_for(|| worker.snapshot().config_changes.is_empty()));
+        assert_eq!(worker.snapshot().config.list, edited.list);
+        assert!(!backend.state(&game).muted);
+    }
+
+    #[test]
+    fn instance_binding_survives_a_stale_config() {
+        let backend = Arc::new(FakeBackend::new());
+        let game = backend.add_session("game.exe", 7);
+        backend.set_process_start(&game, Some(5_000));
+        let foreground = Arc::new(AtomicU32::new(20));
+
+        // 旧版本保存的实例条目，没有绑定启动时间
+        let mut config = Config::default();
+        config.list.insert("game.exe [7]".to_owned());
+
+        let mut worker = {
+            let backend = Arc::clone(&backend);
+            let foreground = Arc::clone(&foreground);
+            let config = config.clone();
+            Worker::spawn_with(
+                move |_| {
+                    let audio = AudioManager::with_backend(Box::new(backend));
+                    Ok(Engine::with_parts(config, audio, Box::new(TestProvider(foreground))))
+                },
+                Arc::new(|| {}),
+            )
+            .unwrap()
+        };
+        // 开始运行时绑定条目，紧接着界面发出一份基于未绑定配置的修改
+        worker.send(Command::SetRunning(true));
+        let mut edited = config.clone();
+        edited.list.insert("chat.exe".to_owned());
+        worker.send(Command::SetConfig { config: edited, revision: 0 });
+        assert!(wait_for(|| worker.snapshot().config.list.contains("chat.exe")));
+        let snapshot = worker.snapshot().clone();
+        assert_eq!(snapshot.config.options("game.exe [7]").process_start_ms, Some(5_000));
+        assert!(matches!(
+            snapshot.config_changes.as_slice(),
+            [(_, ConfigChange::BindInstance { entry, process_start_ms: 5_000 })] if entry == "game.exe [7]"
+        ));
+        assert!(backend.state(&game).muted);
+    }
+
+    #[test]
+    fn initialization_errors_are_reported() {
+        let result = Worker::spawn_with(|_| Err(audio::AudioError::Unsupported), Arc::new(|| {}));
+        assert!(matches!(result, Err(audio::AudioError::Unsupported)));
+    }
+}
@@ -1,65 +1,35 @@
 #![windows_subsystem = "windows"]
 
-mod audio;
-mod utils;
+use std::sync::Arc;
 
-use audio::AudioManager;
 use eframe::egui;
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::sync::mpsc::{channel, Receiver, Sender};
-use std::sync::OnceLock;
-use windows::Win32::Foundation::*;
-use windows::Win32::UI::Accessibility::*;
-use windows::Win32::UI::WindowsAndMessaging::*;
-
-#[derive(Serialize, Deserialize, Clone)]
-struct Config {
-    list: HashSet<String>,
-    is_whitelist: bool,
-}
-
-impl Default for Config {
-    fn default() -> Self {
-        Self {
-            list: HashSet::new(),
-            is_whitelist: false,
-        }
-    }
-}
+use silencer_core::process::{self, ProcessInfoProvider, ProcessKind};
+use silencer_core::config::{AppGroup, BackgroundAction, ExternalChangePolicy, Fade, FadeCurve, ForegroundRelations, GroupMode, RuleOptions};
+use silencer_core::{decision, pattern};
+use silencer_core::{Command, Config, Worker};
 
 struct SilencerApp {
+    worker: Worker,
     config: Config,
-    is_running: bool,
-    audio_manager: AudioManager,
+    // 已合并到界面配置中的引擎修改的版本号（见 silencer_core::engine::ConfigChange）
+    config_revision: u64,
     new_item: String,
-    rx: Receiver<()>,
-    hook_handle: Option<windows::Win32::UI::Accessibility::HWINEVENTHOOK>,
-    active_sessions: Vec<audio::AudioSessionInfo>,
-    last_refresh: std::time::Instant,
-    last_audio_enforcement: std::time::Instant,
+    // 手动添加的条目无法编译时的错误信息
+    new_item_error: Option<String>,
+    new_group: String,
+    // 各分组待添加的成员，按分组名称索引
+    new_members: std::collections::HashMap<String, String>,
+    // 新建分组或添加成员失败时的错误信息
+    group_error: Option<String>,
+    process_info: Box<dyn ProcessInfoProvider>,
     show_sponsor: bool,
     show_help: bool,
+    // 启动修复提示是否已关闭
+    repairs_dismissed: bool,
     wechat_qr: Option<egui::TextureHandle>,
     alipay_qr: Option<egui::TextureHandle>,
 }
 
-static EVENT_SENDER: OnceLock<Sender<()>> = OnceLock::new();
-
-unsafe extern "system" fn win_event_callback(
-    _: windows::Win32::UI::Accessibility::HWINEVENTHOOK,
-    _: u32,
-    _: HWND,
-    _: i32,
-    _: i32,
-    _: u32,
-    _: u32,
-) {
-    if let Some(sender) = EVENT_SENDER.get() {
-        let _ = sender.send(());
-    }
-}
-
 impl SilencerApp {
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Initialize image loaders
@@ -97,31 +67,15 @@ impl SilencerApp {
         }
         cc.egui_ctx.set_fonts(fonts);
 
-        let config = cc.storage
+        let config: Config = cc.storage
             .and_then(|s| s.get_string(eframe::APP_KEY))
             .and_then(|s| serde_json::from_str(&s).ok())
             .unwrap_or_default();
 
-        let (tx, rx) = channel();
-        let mut hook_handle = None;
-        let _ = EVENT_SENDER.set(tx);
-        unsafe {
-            let handle = SetWinEventHook(
-                EVENT_SYSTEM_FOREGROUND,
-                EVENT_SYSTEM_FOREGROUND,
-                None,
-                Some(win_event_callback),
-                0,
-                0,
-                WINEVENT_OUTOFCONTEXT,
-            );
-            if !handle.is_invalid() {
-                hook_handle = Some(handle);
-            }
-        }
-
-        let audio_manager = AudioManager::new().expect("Failed to initialize audio manager");
-        let active_sessions = audio_manager.get_active_sessions().unwrap_or_default();
+        // 静音同步在后台线程上进行，界面只在状态变化时重绘
+        let repaint_ctx = cc.egui_ctx.clone();
+        let worker = Worker::spawn(config.clone(), Arc::new(move || repaint_ctx.request_repaint()))
+            .expect("Failed to initialize audio manager");
 
         // Load QR codes manually to ensure they display
         let wechat_qr = {
@@ -157,49 +111,149 @@ impl SilencerApp {
         };
 
         Self {
+            worker,
             config,
-            is_running: false,
-            audio_manager,
+            config_revision: 0,
             new_item: String::new(),
-            rx,
-            hook_handle,
-            active_sessions,
-            last_refresh: std::time::Instant::now(),
-            last_audio_enforcement: std::time::Instant::now(),
+            new_item_error: None,
+            new_group: String::new(),
+            new_members: Default::default(),
+            group_error: None,
+            process_info: process::platform_provider(),
             show_sponsor: false,
             show_help: false,
+            repairs_dismissed: false,
             wechat_qr,
             alipay_qr,
         }
     }
 
-    fn refresh_sessions(&mut self) {
-        if let Ok(sessions) = self.audio_manager.get_active_sessions() {
-            self.active_sessions = sessions;
-        }
-        self.last_refresh = std::time::Instant::now();
+    /// 将修改后的配置交给后台线程立即生效
+    fn apply_config(&self) {
+        self.worker.send(Command::SetConfig { config: self.config.clone(), revision: self.config_revision });
     }
 
-    fn update_audio(&self) {
-        let foreground_pid = utils::get_foreground_pid();
-        let _ = self.audio_manager.update_mute_status(
-            &self.config.list,
-            self.config.is_whitelist,
-            self.is_running,
-            foreground_pid,
-        );
-    }
-}
+    /// 管理列表中的联动分组编辑区
+    fn groups_editor(&mut self, ui: &mut egui::Ui) {
+        ui.label(egui::RichText::new("联动分组").strong())
+            .on_hover_text("同组的程序一起保持有声，成员的写法与列表条目相同");
+        let mut changed = false;
+        let mut to_remove = None;
+        for (index, group) in self.config.groups.iter_mut().enumerate() {
+            egui::Frame::none()
+                .fill(ui.visuals().widgets.inactive.bg_fill)
+                .rounding(5.0)
+                .inner_margin(5.0)
+                .show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(&group.name).strong());
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("🗑").clicked() {
+                                to_remove = Some(index);
+                            }
+                            let mode_name = |mode: &GroupMode| match mode {
+                                GroupMode::AnyFocused => "任一成员在前台时".to_string(),
+                                GroupMode::FollowMember(leader) => format!("跟随 {}", leader),
+                            };
+                            let previous = group.mode.clone();
+                            egui::ComboBox::from_id_salt(("group_mode", index))
+                                .selected_text(mode_name(&group.mode))
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut group.mode, GroupMode::AnyFocused, mode_name(&GroupMode::AnyFocused));
+                                    for member in &group.members {
+                                        let mode = GroupMode::FollowMember(member.clone());
+                                        let name = mode_name(&mode);
+                                        ui.selectable_value(&mut group.mode, mode, name);
+                                    }
+                                });
+                            changed |= group.mode != previous;
+                        });
+                    });
+
+                    let mut removed_member = None;
+                    for member in &group.members {
+                        ui.horizontal(|ui| {
+                            ui.label(format!("• {}", member));
+                            if ui.small_button("✖").clicked() {
+                                removed_member = Some(member.clone());
+                            }
+                        });
+                    }
+                    if let Some(member) = removed_member {
+                        group.remove_member(&member);
+                        changed = true;
+                    }
 
-impl Drop for SilencerApp {
-    fn drop(&mut self) {
-        if let Some(handle) = self.hook_handle {
-            unsafe {
-                let _ = windows::Win32::UI::Accessibility::UnhookWinEvent(handle);
+                    ui.horizontal(|ui| {
+                        let input = self.new_members.entry(group.name.clone()).or_default();
+                        ui.add(egui::TextEdit::singleline(input).desired_width(150.0).hint_text("成员"));
+                        if ui.button("添加成员").clicked() && !input.is_empty() {
+                            match group.add_member(input) {
+                                Ok(()) => {
+                                    input.clear();
+                                    self.group_error = None;
+                                    changed = true;
+                                }
+                                Err(e) => self.group_error = Some(e.to_string()),
+                            }
+                        }
+                    });
+                });
+            ui.add_space(4.0);
+        }
+        if let Some(index) = to_remove {
+            let group = self.config.groups.remove(index);
+            self.new_members.remove(&group.name);
+            changed = true;
+        }
+
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut self.new_group).desired_width(150.0).hint_text("分组名称"));
+            if ui.button("新建分组").clicked() {
+                let name = self.new_group.trim();
+                if name.is_empty() {
+                    self.group_error = Some("分组名称不能为空".to_string());
+                } else if self.config.groups.iter().any(|group| group.name == name) {
+                    self.group_error = Some(format!("已存在名为 {} 的分组", name));
+                } else {
+                    self.config.groups.push(AppGroup::new(name));
+                    self.new_group.clear();
+                    self.group_error = None;
+                    changed = true;
+                }
             }
+        });
+        if let Some(error) = &self.group_error {
+            ui.label(egui::RichText::new(error).size(11.0).color(egui::Color32::LIGHT_RED));
+        }
+
+        if changed {
+            self.apply_config();
+        }
+    }
+
+    /// 会话卡片悬停时显示的进程详情
+    fn process_details(&self, ui: &mut egui::Ui, pid: u32) {
+        let Some(info) = self.process_info.process_info(pid) else {
+            ui.label("进程已退出");
+            return;
+        };
+        match info.kind {
+            ProcessKind::Zombie => { ui.label("⚠ 僵尸进程（已退出）"); }
+            ProcessKind::KernelThread => { ui.label("内核线程"); }
+            ProcessKind::Normal => {}
+        }
+        ui.label(format!("路径: {}", info.exe_path.as_ref().map(|p| p.display().to_string()).unwrap_or_else(|| "无法读取".to_string())));
+        if !info.cmdline.is_empty() {
+            ui.label(format!("命令行: {}", info.cmdline.join(" ")));
+        }
+        if let Some(parent_pid) = info.parent_pid {
+            ui.label(format!("父进程 PID: {}", parent_pid));
+        }
+        if let Some(elapsed) = info.start_time.and_then(|t| t.elapsed().ok()) {
+            ui.label(format!("已运行: {} 秒", elapsed.as_secs()));
         }
-        // 在应用退出时尝试将我们修改过的会话恢复到原始静音状态
-        let _ = self.audio_manager.restore_saved_states();
     }
 }
 
@@ -292,6 +346,15 @@ impl eframe::App for SilencerApp {
 
                         ui.add_space(10.0);
 
+                        ui.collapsing("🎚 手动调整音量合成器", |ui| {
+                            ui.label("在系统音量合成器中手动取消（或开启）某个后台应用的静音时：");
+                            ui.label("🔁 重新应用规则：以列表为准，短时间内恢复规则设定的状态。");
+                            ui.label("⏸ 暂停至切换前台：保留你的修改，直到前台窗口切换后再按规则处理。");
+                            ui.label("📝 记为新规则：自动把该应用加入或移出列表，使规则与你的修改一致。");
+                        });
+
+                        ui.add_space(10.0);
+
                         ui.collapsing("🔍 添加应用 vs 添加实例", |ui| {
                             ui.strong("1. 📦 添加应用 (按进程名)");
                             ui.label("🎯 范围：控制该软件的所有窗口。");
@@ -304,29 +367,64 @@ impl eframe::App for SilencerApp {
                             ui.label("🎯 范围：仅控制当前选中的这一个特定窗口。");
                             ui.label("💡 场景：适合多开游戏。可以实现“大号有声，小号静音”的精准控制。");
                             ui.label("⏳ 持久性：仅本次运行有效（PID 重启会变）。");
+
+                            ui.add_space(5.0);
+
+                            ui.strong("3. ✳ 手动添加模式");
+                            ui.label("🎯 通配符：*steam* 匹配所有名称含 steam 的进程，? 匹配单个字符。");
+                            ui.label("🎯 正则表达式：用 / 包围，例如 /game-win64-.*\\.exe/，需匹配整个进程名。");
+                            ui.label("🎯 路径：path:D:\\Games\\launcher.exe 只匹配该程序，path:D:\\Games\\ 匹配该目录下的所有程序。");
+                            ui.label("🎯 发布者：company:Valve Corporation 或 product:Steam，按程序版本信息匹配。");
+                            ui.label("🎯 窗口标题：title:YouTube 匹配标题包含该文本的程序，title:/正则/ 在标题中查找，标题变化后自动重新判断。");
+                            ui.label("🔉 后台动作：默认静音，也可以把音量降低到原音量的百分之几，或限制音量上限；列表条目可单独设置动作，停止运行时恢复原音量。");
+                            ui.label("⏳ 延迟静音：在列表条目上设置秒数，程序失去前台后先保持有声，期间切回前台不会静音，适合短暂点开聊天弹窗或任务栏的情况。");
+                            ui.label("🎚 渐变：在顶部设置淡出、淡入的用时与曲线，切换前台时音量逐渐变化而不是突然静音；渐变中再次切换会从当前音量反向变化。");
+                            ui.label("🔗 联动分组：在管理列表下方新建分组并添加成员，可选择“任一成员在前台时”全组不静音，或“跟随”某个成员：它有声时其它成员也有声。");
+                            ui.label("💡 均不区分大小写，列表中会显示每个模式当前命中的会话。");
                         });
                     });
                 });
         }
 
-        if self.last_refresh.elapsed().as_secs() >= 2 {
-            self.refresh_sessions();
+        // 防抖 + 周期同步由后台线程处理（见 silencer_core::worker），这里只读取最新状态
+        let snapshot = self.worker.snapshot().clone();
+        // 引擎自己修改了配置（例如把外部修改记为规则）：合并到界面的配置中，保留界面还没被引擎收到的修改，再整份发回
+        let changes: Vec<_> = snapshot.config_changes.iter().filter(|(revision, _)| *revision > self.config_revision).collect();
+        if let Some((revision, _)) = changes.last() {
+            for (_, change) in &changes {
+                change.apply(&mut self.config);
+            }
+            self.config_revision = *revision;
+            self.apply_config();
         }
-
-        let mut event_triggered = false;
-        while self.rx.try_recv().is_ok() {
-            event_triggered = true;
+        // 当前会话所在的播放设备 (ID, 名称)，按首次出现的顺序
+        let mut devices: Vec<(String, String)> = Vec::new();
+        for session in &snapshot.sessions {
+            if !devices.iter().any(|(id, _)| *id == session.device_id) {
+                devices.push((session.device_id.clone(), session.device_name.clone()));
+            }
         }
 
-        // 核心修复：
-        // 1. 增加 50ms 的防抖（Debounce），防止极速切屏时的性能抖动
-        // 2. 增加 200ms 的周期性强制同步，确保即使错过事件也能恢复正确状态
-        let now = std::time::Instant::now();
-        if self.is_running {
-            let elapsed = now.duration_since(self.last_audio_enforcement).as_millis();
-            if (event_triggered && elapsed >= 50) || elapsed >= 200 {
-                self.update_audio();
-                self.last_audio_enforcement = now;
+        if !snapshot.repairs.is_empty() && !self.repairs_dismissed {
+            let mut open = true;
+            egui::Window::new("🛠 已恢复上次遗留的静音")
+                .open(&mut open)
+                .resizable(false)
+                .collapsible(false)
+                .show(ctx, |ui| {
+                    ui.label("上次运行未能正常退出，以下会话的静音状态已恢复：");
+                    ui.add_space(5.0);
+                    for repair in &snapshot.repairs {
+                        let state = if repair.restored.muted { "保持静音" } else { "已取消静音" };
+                        ui.label(format!("• {} (PID: {}) — {}", repair.process_name, repair.pid, state));
+                    }
+                    ui.add_space(5.0);
+                    if ui.button("知道了").clicked() {
+                        self.repairs_dismissed = true;
+                    }
+                });
+            if !open {
+                self.repairs_dismissed = true;
             }
         }
 
@@ -339,22 +437,22 @@ impl eframe::App for SilencerApp {
                 .inner_margin(15.0)
                 .show(ui, |ui| {
                     ui.horizontal(|ui| {
-                        let button_text = if self.is_running { "停止运行" } else { "开始运行" };
-                        let button_color = if self.is_running { egui::Color32::from_rgb(200, 50, 50) } else { egui::Color32::from_rgb(50, 150, 50) };
+                        let is_running = snapshot.running;
+                        let button_text = if is_running { "停止运行" } else { "开始运行" };
+                        let button_color = if is_running { egui::Color32::from_rgb(200, 50, 50) } else { egui::Color32::from_rgb(50, 150, 50) };
                         
                         if ui.add(egui::Button::new(egui::RichText::new(button_text).color(egui::Color32::WHITE).strong())
                             .fill(button_color)
                             .min_size(egui::vec2(100.0, 35.0))).clicked() {
-                            self.is_running = !self.is_running;
-                            self.update_audio();
+                            self.worker.send(Command::SetRunning(!is_running));
                         }
 
                         ui.add_space(10.0);
-                        ui.label(egui::RichText::new(format!("状态: {}", if self.is_running { "正在运行" } else { "已停止" })).size(16.0));
+                        ui.label(egui::RichText::new(format!("状态: {}", if snapshot.running { "正在运行" } else { "已停止" })).size(16.0));
                         
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                             if ui.button("🔄 刷新列表").clicked() {
-                                self.refresh_sessions();
+                                self.worker.send(Command::RefreshSessions);
                             }
                         });
                     });
@@ -366,14 +464,71 @@ impl eframe::App for SilencerApp {
             ui.horizontal(|ui| {
                 ui.label(egui::RichText::new("工作模式:").size(16.0));
                 ui.add_space(10.0);
-                if ui.selectable_label(!self.config.is_whitelist, "🚫 黑名单模式").clicked() {
+                let is_whitelist = self.config.is_whitelist;
+                if ui.selectable_label(!is_whitelist, "🚫 黑名单模式").clicked() {
                     self.config.is_whitelist = false;
-                    self.update_audio();
+                    self.apply_config();
                 }
                 ui.add_space(5.0);
-                if ui.selectable_label(self.config.is_whitelist, "✅ 白名单模式").clicked() {
+                if ui.selectable_label(is_whitelist, "✅ 白名单模式").clicked() {
                     self.config.is_whitelist = true;
-                    self.update_audio();
+                    self.apply_config();
+                }
+
+                ui.add_space(20.0);
+                ui.label("手动调整混音器时:");
+                let policy_name = |policy: ExternalChangePolicy| match policy {
+                    ExternalChangePolicy::Override => "重新应用规则",
+                    ExternalChangePolicy::PauseUntilFocusChange => "暂停至切换前台",
+                    ExternalChangePolicy::AdoptAsRule => "记为新规则",
+                };
+                let mut policy = self.config.external_change_policy;
+                egui::ComboBox::from_id_salt("external_change_policy")
+                    .selected_text(policy_name(policy))
+                    .show_ui(ui, |ui| {
+                        for option in [
+                            ExternalChangePolicy::Override,
+                            ExternalChangePolicy::PauseUntilFocusChange,
+                            ExternalChangePolicy::AdoptAsRule,
+                        ] {
+                            ui.selectable_value(&mut policy, option, policy_name(option));
+                        }
+                    });
+                if policy != self.config.external_change_policy {
+                    self.config.external_change_policy = policy;
+                    self.apply_config();
+                }
+
+                ui.add_space(20.0);
+                ui.label("后台动作:");
+                let mut action = Some(self.config.background_action);
+                if action_selector(ui, "background_action", &mut action, false) {
+                    self.config.background_action = action.unwrap_or_default();
+                    self.apply_config();
+                }
+
+                ui.add_space(20.0);
+                let mut relations = self.config.foreground_relations;
+                ui.menu_button("🌳 前台范围", |ui| relations_editor(ui, &mut relations))
+                    .response
+                    .on_hover_text("与前台程序有这些关系的进程也视为在前台，适用于所有会话");
+                if relations != self.config.foreground_relations {
+                    self.config.foreground_relations = relations;
+                    self.apply_config();
+                }
+
+                ui.add_space(20.0);
+                let (mut fade_out, mut fade_in) = (self.config.fade_out, self.config.fade_in);
+                ui.menu_button("🎚 渐变", |ui| {
+                    fade_editor(ui, "淡出", &mut fade_out);
+                    fade_editor(ui, "淡入", &mut fade_in);
+                })
+                .response
+                .on_hover_text("切到后台时逐渐降低音量，回到前台时逐渐恢复；用时为 0 时立即切换");
+                if (fade_out, fade_in) != (self.config.fade_out, self.config.fade_in) {
+                    self.config.fade_out = fade_out;
+                    self.config.fade_in = fade_in;
+                    self.apply_config();
                 }
             });
 
@@ -391,42 +546,60 @@ impl eframe::App for SilencerApp {
                         .id_salt("active_sessions")
                         .auto_shrink([false; 2])
                         .show(ui, |ui| {
-                            for session in &self.active_sessions {
-                                egui::Frame::none()
-                                    .fill(ui.visuals().widgets.inactive.bg_fill)
-                                    .rounding(8.0)
-                                    .inner_margin(10.0)
-                                    .show(ui, |ui| {
-                                        ui.set_width(ui.available_width());
-                                        ui.vertical(|ui| {
-                                            ui.horizontal(|ui| {
-                                                ui.label(egui::RichText::new(&session.display_name).strong());
-                                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                                                    ui.label(egui::RichText::new(format!("PID: {}", session.pid)).size(10.0).color(egui::Color32::GRAY));
+                            // 按播放设备分组
+                            for (device_id, device_name) in &devices {
+                                ui.label(egui::RichText::new(format!("🔊 {}", device_name)).strong().color(egui::Color32::GRAY));
+                                ui.add_space(4.0);
+                                for session in snapshot.sessions.iter().filter(|s| &s.device_id == device_id) {
+                                    egui::Frame::none()
+                                        .fill(ui.visuals().widgets.inactive.bg_fill)
+                                        .rounding(8.0)
+                                        .inner_margin(10.0)
+                                        .show(ui, |ui| {
+                                            ui.set_width(ui.available_width());
+                                            ui.vertical(|ui| {
+                                                ui.horizontal(|ui| {
+                                                    ui.label(egui::RichText::new(&session.display_name).strong())
+                                                        .on_hover_ui(|ui| self.process_details(ui, session.pid));
+                                                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                                        ui.label(egui::RichText::new(format!("PID: {}", session.pid)).size(10.0).color(egui::Color32::GRAY));
+                                                    });
                                                 });
-                                            });
-                                            
-                                            if !session.window_title.is_empty() {
-                                                ui.label(egui::RichText::new(&session.window_title).size(11.0).color(egui::Color32::LIGHT_GRAY));
-                                            }
                                             
-                                            ui.add_space(5.0);
-                                            ui.horizontal(|ui| {
-                                                if ui.button("📦 添加应用").clicked() {
-                                                    to_add = Some(session.name.clone());
-                                                }
-                                                if ui.button("🆔 添加实例").clicked() {
-                                                    to_add = Some(format!("{} [{}]", session.name, session.pid));
+                                                if !session.window_title.is_empty() {
+                                                    ui.label(egui::RichText::new(&session.window_title).size(11.0).color(egui::Color32::LIGHT_GRAY));
                                                 }
+                                            
+                                                ui.add_space(5.0);
+                                                ui.horizontal(|ui| {
+                                                    if ui.button("📦 添加应用").clicked() {
+                                                        to_add = Some((session.name.clone(), RuleOptions::default()));
+                                                    }
+                                                    if ui.button("🆔 添加实例").clicked() {
+                                                        // 绑定进程启动时间，PID 被复用后不会误伤其它进程
+                                                        to_add = Some(decision::instance_entry(session));
+                                                    }
+                                                    if let Some(entry) = decision::path_entry(session)
+                                                        && ui.button("📁 按路径").on_hover_text(&entry).clicked()
+                                                    {
+                                                        to_add = Some((entry, RuleOptions::default()));
+                                                    }
+                                                    if let Some(entry) = decision::publisher_entry(session)
+                                                        && ui.button("🏢 按发布者").on_hover_text(&entry).clicked()
+                                                    {
+                                                        to_add = Some((entry, RuleOptions::default()));
+                                                    }
+                                                });
                                             });
                                         });
-                                    });
-                                ui.add_space(8.0);
+                                    ui.add_space(8.0);
+                                }
                             }
                         });
-                    if let Some(item) = to_add {
-                        self.config.list.insert(item);
-                        self.update_audio();
+                    if let Some((item, options)) = to_add {
+                        self.config.list.insert(item.clone());
+                        self.config.set_options(&item, options);
+                        self.apply_config();
                     }
                 });
 
@@ -436,6 +609,8 @@ impl eframe::App for SilencerApp {
                     ui.add_space(5.0);
 
                     let mut to_remove = None;
+                    let mut changed_options = None;
+                    let is_whitelist = self.config.is_whitelist;
                     egui::ScrollArea::vertical()
                         .id_salt("mute_list")
                         .auto_shrink([false; 2])
@@ -448,14 +623,51 @@ impl eframe::App for SilencerApp {
                                         .inner_margin(5.0)
                                         .show(ui, |ui| {
                                             ui.set_width(ui.available_width());
+                                            let mut options = self.config.options(item);
                                             ui.horizontal(|ui| {
                                                 ui.label(item);
                                                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                                                     if ui.button("🗑").clicked() {
                                                         to_remove = Some(item.clone());
                                                     }
+                                                    let previous = options.foreground_relations;
+                                                    // 白名单中的条目本来就不会被静音，条目上的前台关系不起作用，只能使用全局的“前台范围”
+                                                    if !is_whitelist {
+                                                        ui.menu_button("🌳", |ui| relations_editor(ui, &mut options.foreground_relations))
+                                                            .response
+                                                            .on_hover_text("命中该条目的进程与前台程序有这些关系时也视为在前台");
+                                                    }
+                                                    let delay_changed = ui
+                                                        .add(egui::DragValue::new(&mut options.mute_delay_secs).range(0..=600).suffix(" 秒"))
+                                                        .on_hover_text("失去前台后保持有声的时间，期间切回前台不会静音")
+                                                        .changed();
+                                                    let action_changed = action_selector(ui, ("rule_action", item), &mut options.action, true);
+                                                    if device_selector(ui, item, &mut options.device, &devices)
+                                                        || options.foreground_relations != previous
+                                                        || action_changed
+                                                        || delay_changed
+                                                    {
+                                                        changed_options = Some((item.clone(), options.clone()));
+                                                    }
                                                 });
                                             });
+                                            // 通配符与正则表达式条目显示当前命中的会话
+                                            match pattern::compiled(item) {
+                                                None => {
+                                                    ui.label(egui::RichText::new("无效的模式").size(10.0).color(egui::Color32::LIGHT_RED));
+                                                }
+                                                Some(compiled) if !compiled.is_name() => {
+                                                    let matched: Vec<&str> = snapshot
+                                                        .sessions
+                                                        .iter()
+                                                        .filter(|session| decision::entry_matches(item, &options, session))
+                                                        .map(|session| session.display_name.as_str())
+                                                        .collect();
+                                                    let text = if matched.is_empty() { "当前没有命中的会话".to_string() } else { format!("命中: {}", matched.join(", ")) };
+                                                    ui.label(egui::RichText::new(text).size(10.0).color(egui::Color32::GRAY));
+                                                }
+                                                Some(_) => {}
+                                            }
                                         });
                                 });
                                 ui.add_space(4.0);
@@ -463,8 +675,12 @@ impl eframe::App for SilencerApp {
                         });
 
                     if let Some(item) = to_remove {
-                        self.config.list.remove(&item);
-                        self.update_audio();
+                        self.config.remove(&item);
+                        self.apply_config();
+                    }
+                    if let Some((item, options)) = changed_options {
+                        self.config.set_options(&item, options);
+                        self.apply_config();
                     }
 
                     ui.add_space(10.0);
@@ -472,18 +688,29 @@ impl eframe::App for SilencerApp {
                     ui.add_space(5.0);
                     ui.label("手动添加:");
                     ui.horizontal(|ui| {
-                        ui.text_edit_singleline(&mut self.new_item);
+                        ui.text_edit_singleline(&mut self.new_item)
+                            .on_hover_text("进程名，或通配符（如 *steam*），或 /正则表达式/，或 path:路径、company:公司、product:产品、title:窗口标题");
                         if ui.button("添加").clicked() && !self.new_item.is_empty() {
-                            self.config.list.insert(self.new_item.clone());
-                            self.new_item.clear();
-                            self.update_audio();
+                            match self.config.add(&self.new_item) {
+                                Ok(()) => {
+                                    self.new_item.clear();
+                                    self.new_item_error = None;
+                                    self.apply_config();
+                                }
+                                Err(e) => self.new_item_error = Some(e.to_string()),
+                            }
                         }
                     });
+                    if let Some(error) = &self.new_item_error {
+                        ui.label(egui::RichText::new(error).size(11.0).color(egui::Color32::LIGHT_RED));
+                    }
+
+                    ui.add_space(10.0);
+                    ui.separator();
+                    self.groups_editor(ui);
                 });
             });
         });
-        
-        ctx.request_repaint_after(std::time::Duration::from_millis(500));
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
@@ -493,6 +720,91 @@ impl eframe::App for SilencerApp {
     }
 }
 
+/// 后台动作选择框，`allow_default` 时可以选择 "默认"（`None`，使用全局设置）。返回是否修改了动作
+fn action_selector(ui: &mut egui::Ui, id_salt: impl std::hash::Hash, action: &mut Option<BackgroundAction>, allow_default: bool) -> bool {
+    let name = |action: &Option<BackgroundAction>| match action {
+        None => "默认",
+        Some(BackgroundAction::Mute) => "静音",
+        Some(BackgroundAction::Duck(_)) => "降低音量",
+        Some(BackgroundAction::Cap(_)) => "音量上限",
+    };
+    let previous = *action;
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_salt(id_salt)
+            .selected_text(name(action))
+            .show_ui(ui, |ui| {
+                if allow_default {
+                    ui.selectable_value(action, None, name(&None));
+                }
+                for option in [BackgroundAction::Mute, BackgroundAction::Duck(20), BackgroundAction::Cap(30)] {
+                    // 百分比不同也算同一种动作
+                    let selected = action.is_some_and(|current| std::mem::discriminant(&current) == std::mem::discriminant(&option));
+                    if ui.selectable_label(selected, name(&Some(option))).clicked() && !selected {
+                        *action = Some(option);
+                    }
+                }
+            });
+        if let Some(BackgroundAction::Duck(percent) | BackgroundAction::Cap(percent)) = action {
+            ui.add(egui::DragValue::new(percent).range(0..=100).suffix("%"));
+        }
+    });
+    *action != previous
+}
+
+/// 一段渐变的用时与曲线
+fn fade_editor(ui: &mut egui::Ui, label: &str, fade: &mut Fade) {
+    let name = |curve: FadeCurve| match curve {
+        FadeCurve::Linear => "线性",
+        FadeCurve::EaseIn => "缓入",
+        FadeCurve::EaseOut => "缓出",
+        FadeCurve::EaseInOut => "缓入缓出",
+    };
+    ui.horizontal(|ui| {
+        ui.label(format!("{}:", label));
+        ui.add(egui::DragValue::new(&mut fade.duration_ms).range(0..=5000).speed(10).suffix(" ms"));
+        egui::ComboBox::from_id_salt(("fade_curve", label))
+            .selected_text(name(fade.curve))
+            .show_ui(ui, |ui| {
+                for curve in [FadeCurve::Linear, FadeCurve::EaseIn, FadeCurve::EaseOut, FadeCurve::EaseInOut] {
+                    ui.selectable_value(&mut fade.curve, curve, name(curve));
+                }
+            });
+    });
+}
+
+/// 前台关系的复选框
+fn relations_editor(ui: &mut egui::Ui, relations: &mut ForegroundRelations) {
+    ui.checkbox(&mut relations.descendants, "子进程（如浏览器的音频服务进程）");
+    ui.checkbox(&mut relations.ancestors, "父进程（如拉起游戏的启动器）");
+    // Windows 无法查询其它进程所属的作业对象，进程组关系不会命中
+    #[cfg(not(windows))]
+    ui.checkbox(&mut relations.same_group, "同一进程组");
+}
+
+/// 规则的目标设备选择框，返回是否修改了选择
+fn device_selector(ui: &mut egui::Ui, item: &str, device: &mut Option<String>, devices: &[(String, String)]) -> bool {
+    let selected_text = match device {
+        None => "所有设备".to_string(),
+        Some(id) => devices
+            .iter()
+            .find(|(device_id, _)| device_id == id)
+            .map(|(_, name)| name.clone())
+            .unwrap_or_else(|| format!("{} (未连接)", id)),
+    };
+
+    let previous = device.clone();
+    egui::ComboBox::from_id_salt(("rule_device", item))
+        .selected_text(selected_text)
+        .width(120.0)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(device, None, "所有设备");
+            for (id, name) in devices {
+                ui.selectable_value(device, Some(id.clone()), name);
+            }
+        });
+    *device != previous
+}
+
 fn main() -> eframe::Result {
     // 构建视口并使用编译时内嵌的 ICO（通过 include_bytes! 保证在可执行文件中存在）
     let mut viewport_builder = egui::ViewportBuilder::default()
@@ -500,8 +812,11 @@ fn main() -> eframe::Result {
         .with_min_inner_size([600.0, 450.0]);
 
     // 使用编译时包含的 ico 数据，确保窗口图标在所有运行环境下一致
-    // 如果仓库根目录有 silencer-rs.ico，该文件会在编译时被包含进可执行文件
-    const EMBEDDED_ICO: &[u8] = include_bytes!("../../silencer-rs.ico");
+    // 如果仓库根目录有 silencer-rs.ico，该文件会在编译时被包含进可执行文件（路径由 build.rs 查找）
+    #[cfg(embedded_icon)]
+    const EMBEDDED_ICO: &[u8] = include_bytes!(env!("SILENCER_ICON"));
+    #[cfg(not(embedded_icon))]
+    const EMBEDDED_ICO: &[u8] = &[];
     if let Ok(img) = image::load_from_memory_with_format(EMBEDDED_ICO, image::ImageFormat::Ico) {
         let rgba = img.to_rgba8();
         let width = rgba.width();